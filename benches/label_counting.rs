@@ -0,0 +1,57 @@
+//! Compares the dense table and hash map paths of `draw_principal_connected_components`.
+//!
+//! Before anything is measured, both paths are checked against `data/principal_components.png`,
+//! which was drawn by the original hash map implementation. Shifting every label by a constant
+//! keeps the ranking, so both inputs must produce that same image.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use image::{ImageBuffer, Luma, Rgba};
+use image_debug_utils::region_labelling::draw_principal_connected_components;
+
+const SIZE: u32 = 1024;
+/// Shifts labels far past the dense table limit, forcing the hash map path.
+const SPARSE_OFFSET: u64 = 1 << 40;
+
+/// Square cells of varying size, so components have distinct areas and ties.
+fn labels(offset: u64) -> ImageBuffer<Luma<u64>, Vec<u64>> {
+    ImageBuffer::from_fn(SIZE, SIZE, |x, y| {
+        let (cx, cy) = (x / 24, y / 24);
+        if (x % 24) < 2 + (cx + cy) % 20 {
+            Luma([offset + (cy * 64 + cx) as u64 + 1])
+        } else {
+            Luma([0])
+        }
+    })
+}
+
+fn label_counting(c: &mut Criterion) {
+    let background = Rgba([0, 0, 0, 255]);
+    let dense = labels(0);
+    let sparse = labels(SPARSE_OFFSET);
+    let expected = image::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/benches/data/principal_components.png"
+    ))
+    .unwrap()
+    .into_rgba8();
+    assert!(
+        draw_principal_connected_components(&dense, 100, background) == expected,
+        "dense path differs from the saved output"
+    );
+    assert!(
+        draw_principal_connected_components(&sparse, 100, background) == expected,
+        "hash map path differs from the saved output"
+    );
+
+    let mut group = c.benchmark_group("draw_principal_connected_components");
+    group.bench_function("dense labels", |b| {
+        b.iter(|| draw_principal_connected_components(black_box(&dense), 100, background))
+    });
+    group.bench_function("sparse labels", |b| {
+        b.iter(|| draw_principal_connected_components(black_box(&sparse), 100, background))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, label_counting);
+criterion_main!(benches);
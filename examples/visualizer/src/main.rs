@@ -4,13 +4,12 @@ use iced::widget::{
 use iced::{Element, Length, Task, Theme};
 use image::{DynamicImage, Luma, Rgb, Rgba};
 use image_debug_utils::{
-    contours::remove_hypotenuse_in_place, rect::to_axis_aligned_bounding_box,
+    contours::remove_hypotenuse_in_place, rect::RotatedRect,
     region_labelling::draw_principal_connected_components,
 };
 use imageproc::{
     contours::{Contour, find_contours},
     drawing::{draw_hollow_rect_mut, draw_line_segment_mut},
-    geometry::min_area_rect,
     region_labelling::connected_components,
 };
 
@@ -651,18 +650,14 @@ fn process_image(img: &DynamicImage, instance: VisualizerInstance) -> ProcessedR
                     if c.points.len() < 10 {
                         return false;
                     }
-                    let rect_points = min_area_rect(&c.points);
-                    let aabb = to_axis_aligned_bounding_box(&rect_points);
+                    let aabb = RotatedRect::enclosing(&c.points).to_axis_aligned_bounding_box();
                     let area = (aabb.width as f64) * (aabb.height as f64);
                     area < total_area * 0.90
                 })
                 .max_by_key(|c| c.points.len())
-                .map(|c| {
-                    let rect_points = min_area_rect(&c.points);
-                    (c, rect_points)
-                });
+                .map(|c| (c, RotatedRect::enclosing(&c.points)));
 
-            if let Some((c, rect_points)) = selected_contour {
+            if let Some((c, rotated_rect)) = selected_contour {
                 // Draw the contour itself (Blue)
                 let blue = Rgb([0, 0, 255]);
                 for i in 0..c.points.len() {
@@ -677,6 +672,7 @@ fn process_image(img: &DynamicImage, instance: VisualizerInstance) -> ProcessedR
                 }
 
                 // Draw OBB (Green)
+                let rect_points = rotated_rect.corners();
                 for i in 0..4 {
                     let p1 = rect_points[i];
                     let p2 = rect_points[(i + 1) % 4];
@@ -689,7 +685,7 @@ fn process_image(img: &DynamicImage, instance: VisualizerInstance) -> ProcessedR
                 }
 
                 // Draw AABB (Red)
-                let aabb = to_axis_aligned_bounding_box(&rect_points);
+                let aabb = rotated_rect.to_axis_aligned_bounding_box();
                 let rect_struct = imageproc::rect::Rect::at(aabb.x as i32, aabb.y as i32)
                     .of_size(aabb.width, aabb.height);
                 draw_hollow_rect_mut(&mut canvas, rect_struct, red);
@@ -0,0 +1,738 @@
+use crate::rect::{rect_from_edges, to_signed_bounding_box};
+use image::math::Rect;
+use imageproc::{
+    contours::{BorderType, Contour},
+    geometry::contour_area,
+    point::Point,
+};
+use num_traits::ToPrimitive;
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Read, Write},
+};
+
+/// A labelled object: a class, a bounding box and an optional polygon outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// The class of the object. For COCO this is the category id, for YOLO the class index
+    /// and for Pascal VOC the index into the list of class names.
+    pub class_id: u32,
+    /// The axis-aligned bounding box of the object.
+    pub bbox: Rect,
+    /// The outline of the object in pixel coordinates, or empty for box-only annotations.
+    pub polygon: Vec<Point<f64>>,
+}
+
+impl Annotation {
+    /// Creates a box-only annotation.
+    pub fn from_rect(rect: Rect, class_id: u32) -> Self {
+        Annotation {
+            class_id,
+            bbox: rect,
+            polygon: Vec::new(),
+        }
+    }
+
+    /// Creates an annotation with the contour as its polygon and the contour's bounding box.
+    ///
+    /// The bounding box is that of `rect::to_signed_bounding_box`, cut off at 0.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the contour has no points or a coordinate cannot be converted.
+    pub fn from_contour<T>(contour: &Contour<T>, class_id: u32) -> Option<Self>
+    where
+        T: Copy + ToPrimitive,
+    {
+        let signed = to_signed_bounding_box(&contour.points)?;
+        let right = signed.left() as i64 + signed.width() as i64;
+        let bottom = signed.top() as i64 + signed.height() as i64;
+        let bbox = rect_from_edges(
+            signed.left() as f64,
+            signed.top() as f64,
+            right as f64,
+            bottom as f64,
+        );
+        let polygon = contour
+            .points
+            .iter()
+            .map(|p| Some(Point::new(p.x.to_f64()?, p.y.to_f64()?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Annotation {
+            class_id,
+            bbox,
+            polygon,
+        })
+    }
+
+    /// Converts the polygon back into an outer contour without parent, rounding every
+    /// point to the nearest pixel. Box-only annotations yield the four box corners.
+    pub fn to_contour(&self) -> Contour<i32> {
+        let points = if self.polygon.is_empty() {
+            box_corners(&self.bbox)
+                .iter()
+                .map(|p| Point::new(p.x as i32, p.y as i32))
+                .collect()
+        } else {
+            self.polygon
+                .iter()
+                .map(|p| Point::new(p.x.round() as i32, p.y.round() as i32))
+                .collect()
+        };
+        Contour::new(points, BorderType::Outer, None)
+    }
+}
+
+/// An image with its annotations, as stored in COCO and Pascal VOC files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedImage {
+    /// The image id. Only used by COCO; Pascal VOC files are read with id 0.
+    pub id: u64,
+    /// The file name of the image.
+    pub file_name: String,
+    /// The image width in pixels.
+    pub width: u32,
+    /// The image height in pixels.
+    pub height: u32,
+    /// The objects in the image.
+    pub annotations: Vec<Annotation>,
+}
+
+/// A COCO category.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Category {
+    /// The category id, referenced by [`Annotation::class_id`].
+    pub id: u32,
+    /// The category name.
+    pub name: String,
+}
+
+/// Writes images and their annotations as a COCO JSON document.
+///
+/// Every annotation gets a `bbox` of `[x, y, width, height]` and, if it has a polygon, a
+/// `segmentation` with that polygon. The `area` is the polygon area, or the box area for
+/// box-only annotations. Annotation ids are assigned sequentially from 1.
+pub fn write_coco<W: Write>(
+    writer: W,
+    images: &[AnnotatedImage],
+    categories: &[Category],
+) -> io::Result<()> {
+    let mut annotation_id = 0u64;
+    let mut annotations = Vec::new();
+    for image in images {
+        for annotation in &image.annotations {
+            annotation_id += 1;
+            let b = annotation.bbox;
+            let (segmentation, area) = if annotation.polygon.is_empty() {
+                (json!([]), b.width as f64 * b.height as f64)
+            } else {
+                let flat: Vec<f64> = annotation.polygon.iter().flat_map(|p| [p.x, p.y]).collect();
+                (json!([flat]), contour_area(&annotation.polygon))
+            };
+            annotations.push(json!({
+                "id": annotation_id,
+                "image_id": image.id,
+                "category_id": annotation.class_id,
+                "bbox": [b.x, b.y, b.width, b.height],
+                "area": area,
+                "segmentation": segmentation,
+                "iscrowd": 0,
+            }));
+        }
+    }
+
+    let document = json!({
+        "images": images
+            .iter()
+            .map(|image| json!({
+                "id": image.id,
+                "file_name": image.file_name,
+                "width": image.width,
+                "height": image.height,
+            }))
+            .collect::<Vec<_>>(),
+        "annotations": annotations,
+        "categories": categories
+            .iter()
+            .map(|category| json!({ "id": category.id, "name": category.name }))
+            .collect::<Vec<_>>(),
+    });
+
+    serde_json::to_writer_pretty(writer, &document).map_err(io::Error::from)
+}
+
+/// Reads a COCO JSON document.
+///
+/// Bounding boxes are rounded outwards to whole pixels. Only polygon segmentations are
+/// loaded; RLE masks are ignored, and annotations with several polygons keep the first.
+///
+/// # Returns
+///
+/// The images, each with its annotations, and the categories.
+///
+/// # Errors
+///
+/// Returns an error of kind `InvalidData` if the document is not valid COCO JSON, if an id
+/// or size does not fit in a `u32`, or if an annotation refers to an unknown image.
+pub fn read_coco<R: Read>(reader: R) -> io::Result<(Vec<AnnotatedImage>, Vec<Category>)> {
+    let document: Value = serde_json::from_reader(reader).map_err(io::Error::from)?;
+
+    let mut images = Vec::new();
+    for image in array(&document, "images")? {
+        images.push(AnnotatedImage {
+            id: uint(image, "id")?,
+            file_name: image
+                .get("file_name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            width: uint32(image, "width")?,
+            height: uint32(image, "height")?,
+            annotations: Vec::new(),
+        });
+    }
+
+    // Annotations of a repeated image id go to the first image with that id.
+    let mut image_indices = HashMap::new();
+    for (index, image) in images.iter().enumerate() {
+        image_indices.entry(image.id).or_insert(index);
+    }
+    for annotation in array(&document, "annotations")? {
+        let image_id = uint(annotation, "image_id")?;
+        let image = image_indices
+            .get(&image_id)
+            .map(|&index| &mut images[index])
+            .ok_or_else(|| {
+                invalid_data(format!("annotation refers to unknown image {image_id}"))
+            })?;
+
+        let bbox: Vec<f64> = annotation
+            .get("bbox")
+            .and_then(Value::as_array)
+            .map(|values| values.iter().filter_map(Value::as_f64).collect())
+            .unwrap_or_default();
+        if bbox.len() != 4 {
+            return Err(invalid_data("annotation without a valid bbox"));
+        }
+
+        let polygon = annotation
+            .get("segmentation")
+            .and_then(Value::as_array)
+            .and_then(|polygons| polygons.first())
+            .and_then(Value::as_array)
+            .map(|flat| {
+                let values: Vec<f64> = flat.iter().filter_map(Value::as_f64).collect();
+                values
+                    .chunks_exact(2)
+                    .map(|xy| Point::new(xy[0], xy[1]))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        image.annotations.push(Annotation {
+            class_id: uint32(annotation, "category_id")?,
+            bbox: rect_from_edges(bbox[0], bbox[1], bbox[0] + bbox[2], bbox[1] + bbox[3]),
+            polygon,
+        });
+    }
+
+    let mut categories = Vec::new();
+    for category in array(&document, "categories")? {
+        categories.push(Category {
+            id: uint32(category, "id")?,
+            name: category
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        });
+    }
+
+    Ok((images, categories))
+}
+
+/// The two flavors of YOLO label files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YoloFormat {
+    /// `class center_x center_y width height` per line (detection).
+    Boxes,
+    /// `class x1 y1 x2 y2 ...` per line (segmentation). Box-only annotations are written as
+    /// their four corners.
+    Polygons,
+}
+
+/// Writes annotations of one image as a YOLO label file, with coordinates normalized by
+/// the image dimensions.
+pub fn write_yolo<W: Write>(
+    mut writer: W,
+    annotations: &[Annotation],
+    image_width: u32,
+    image_height: u32,
+    format: YoloFormat,
+) -> io::Result<()> {
+    let (w, h) = (image_width as f64, image_height as f64);
+    for annotation in annotations {
+        write!(writer, "{}", annotation.class_id)?;
+        match format {
+            YoloFormat::Boxes => {
+                let b = annotation.bbox;
+                let values = [
+                    (b.x as f64 + b.width as f64 / 2.0) / w,
+                    (b.y as f64 + b.height as f64 / 2.0) / h,
+                    b.width as f64 / w,
+                    b.height as f64 / h,
+                ];
+                for value in values {
+                    write!(writer, " {value:.6}")?;
+                }
+            }
+            YoloFormat::Polygons => {
+                let polygon = if annotation.polygon.is_empty() {
+                    box_corners(&annotation.bbox).to_vec()
+                } else {
+                    annotation.polygon.clone()
+                };
+                for p in polygon {
+                    write!(writer, " {:.6} {:.6}", p.x / w, p.y / h)?;
+                }
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads a YOLO label file, converting normalized coordinates back to pixels.
+///
+/// Each line is detected as a box (5 values) or a polygon (an odd number of values, at
+/// least 7) independently, so files mixing both are accepted. Polygon lines also get the
+/// bounding box of their points, rounded outwards. Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns an error of kind `InvalidData` for lines that are neither boxes nor polygons.
+pub fn read_yolo<R: BufRead>(
+    reader: R,
+    image_width: u32,
+    image_height: u32,
+) -> io::Result<Vec<Annotation>> {
+    let (w, h) = (image_width as f64, image_height as f64);
+    let mut annotations = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let Some(class) = fields.next() else {
+            continue;
+        };
+        let class_id: u32 = class
+            .parse()
+            .map_err(|_| invalid_data(format!("invalid class id in line {line:?}")))?;
+        let values = fields
+            .map(str::parse::<f64>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid_data(format!("invalid number in line {line:?}")))?;
+
+        if values.len() == 4 {
+            let (cx, cy, bw, bh) = (values[0] * w, values[1] * h, values[2] * w, values[3] * h);
+            annotations.push(Annotation::from_rect(
+                rect_from_edges(cx - bw / 2.0, cy - bh / 2.0, cx + bw / 2.0, cy + bh / 2.0),
+                class_id,
+            ));
+        } else if values.len() >= 6 && values.len() % 2 == 0 {
+            let polygon: Vec<Point<f64>> = values
+                .chunks_exact(2)
+                .map(|xy| Point::new(xy[0] * w, xy[1] * h))
+                .collect();
+            let (min_x, max_x) = min_max(polygon.iter().map(|p| p.x));
+            let (min_y, max_y) = min_max(polygon.iter().map(|p| p.y));
+            annotations.push(Annotation {
+                class_id,
+                bbox: rect_from_edges(min_x, min_y, max_x, max_y),
+                polygon,
+            });
+        } else {
+            return Err(invalid_data(format!(
+                "expected a box or a polygon in line {line:?}"
+            )));
+        }
+    }
+
+    Ok(annotations)
+}
+
+/// Writes one image and its annotations as a Pascal VOC XML document.
+///
+/// `class_names[class_id]` is used as the object name. Boxes are written as `xmin`, `ymin`,
+/// `xmax = x + width` and `ymax = y + height`, without the 1-based offset of the original
+/// VOC tools. Polygons are not part of the format and are not written.
+///
+/// # Errors
+///
+/// Returns an error of kind `InvalidInput` if a `class_id` has no entry in `class_names`.
+pub fn write_voc<W: Write>(
+    mut writer: W,
+    image: &AnnotatedImage,
+    class_names: &[&str],
+) -> io::Result<()> {
+    writeln!(writer, "<annotation>")?;
+    writeln!(
+        writer,
+        "  <filename>{}</filename>",
+        escape_xml(&image.file_name)
+    )?;
+    writeln!(writer, "  <size>")?;
+    writeln!(writer, "    <width>{}</width>", image.width)?;
+    writeln!(writer, "    <height>{}</height>", image.height)?;
+    writeln!(writer, "    <depth>3</depth>")?;
+    writeln!(writer, "  </size>")?;
+    for annotation in &image.annotations {
+        let name = class_names
+            .get(annotation.class_id as usize)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("no class name for class id {}", annotation.class_id),
+                )
+            })?;
+        let b = annotation.bbox;
+        writeln!(writer, "  <object>")?;
+        writeln!(writer, "    <name>{}</name>", escape_xml(name))?;
+        writeln!(writer, "    <bndbox>")?;
+        writeln!(writer, "      <xmin>{}</xmin>", b.x)?;
+        writeln!(writer, "      <ymin>{}</ymin>", b.y)?;
+        writeln!(writer, "      <xmax>{}</xmax>", b.x as u64 + b.width as u64)?;
+        writeln!(
+            writer,
+            "      <ymax>{}</ymax>",
+            b.y as u64 + b.height as u64
+        )?;
+        writeln!(writer, "    </bndbox>")?;
+        writeln!(writer, "  </object>")?;
+    }
+    writeln!(writer, "</annotation>")
+}
+
+/// Reads a Pascal VOC XML document, mapping object names to their index in `class_names`.
+///
+/// Only the elements written by [`write_voc`] are read (`filename`, `size` and each
+/// object's `name` and `bndbox`); everything else is ignored. Fractional coordinates are
+/// rounded outwards.
+///
+/// # Errors
+///
+/// Returns an error of kind `InvalidData` if a required element is missing or malformed,
+/// if the image size is not a whole number that fits in a `u32`, or if an object name is
+/// not in `class_names`.
+pub fn read_voc<R: Read>(mut reader: R, class_names: &[&str]) -> io::Result<AnnotatedImage> {
+    let mut xml = String::new();
+    reader.read_to_string(&mut xml)?;
+
+    let number = |element: &str, tag: &str| -> io::Result<f64> {
+        xml_element(element, tag)
+            .and_then(|text| text.trim().parse().ok())
+            .ok_or_else(|| invalid_data(format!("missing or invalid <{tag}>")))
+    };
+
+    let whole_number = |element: &str, tag: &str| -> io::Result<u32> {
+        let value = number(element, tag)?;
+        if value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&value) {
+            Ok(value as u32)
+        } else {
+            Err(invalid_data(format!("<{tag}> out of range: {value}")))
+        }
+    };
+
+    let size = xml_element(&xml, "size").ok_or_else(|| invalid_data("missing <size>"))?;
+    let mut image = AnnotatedImage {
+        id: 0,
+        file_name: xml_element(&xml, "filename")
+            .map(|name| unescape_xml(name.trim()))
+            .unwrap_or_default(),
+        width: whole_number(size, "width")?,
+        height: whole_number(size, "height")?,
+        annotations: Vec::new(),
+    };
+
+    let mut rest = xml.as_str();
+    while let Some(start) = rest.find("<object>") {
+        let body_start = start + "<object>".len();
+        let end = rest[body_start..]
+            .find("</object>")
+            .ok_or_else(|| invalid_data("unterminated <object>"))?;
+        let object = &rest[body_start..body_start + end];
+        rest = &rest[body_start + end..];
+
+        let name = xml_element(object, "name")
+            .map(|name| unescape_xml(name.trim()))
+            .ok_or_else(|| invalid_data("object without <name>"))?;
+        let class_id = class_names
+            .iter()
+            .position(|&class_name| class_name == name)
+            .ok_or_else(|| invalid_data(format!("unknown class name {name:?}")))?;
+        let bndbox =
+            xml_element(object, "bndbox").ok_or_else(|| invalid_data("object without <bndbox>"))?;
+
+        image.annotations.push(Annotation::from_rect(
+            rect_from_edges(
+                number(bndbox, "xmin")?,
+                number(bndbox, "ymin")?,
+                number(bndbox, "xmax")?,
+                number(bndbox, "ymax")?,
+            ),
+            u32::try_from(class_id).map_err(|_| invalid_data("too many class names"))?,
+        ));
+    }
+
+    Ok(image)
+}
+
+/// The corners of a box, clockwise from the top-left.
+fn box_corners(b: &Rect) -> [Point<f64>; 4] {
+    let (left, top) = (b.x as f64, b.y as f64);
+    let (right, bottom) = (left + b.width as f64, top + b.height as f64);
+    [
+        Point::new(left, top),
+        Point::new(right, top),
+        Point::new(right, bottom),
+        Point::new(left, bottom),
+    ]
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    })
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn array<'a>(document: &'a Value, key: &str) -> io::Result<&'a Vec<Value>> {
+    document
+        .get(key)
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid_data(format!("missing \"{key}\" array")))
+}
+
+fn uint(object: &Value, key: &str) -> io::Result<u64> {
+    object
+        .get(key)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid_data(format!("missing or invalid \"{key}\"")))
+}
+
+fn uint32(object: &Value, key: &str) -> io::Result<u32> {
+    u32::try_from(uint(object, key)?)
+        .map_err(|_| invalid_data(format!("\"{key}\" does not fit in 32 bits")))
+}
+
+/// Returns the text between the first `<tag>` and the following `</tag>`.
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)?;
+    Some(&xml[start..start + end])
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::rect;
+
+    fn sample_image() -> AnnotatedImage {
+        let contour = Contour::new(
+            vec![
+                Point::new(10, 10),
+                Point::new(30, 10),
+                Point::new(30, 20),
+                Point::new(10, 20),
+            ],
+            BorderType::Outer,
+            None,
+        );
+        AnnotatedImage {
+            id: 7,
+            file_name: "plate & wells.png".to_string(),
+            width: 100,
+            height: 50,
+            annotations: vec![
+                Annotation::from_contour(&contour, 1).unwrap(),
+                Annotation::from_rect(rect(50, 5, 20, 40), 0),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_annotation_from_contour() {
+        let annotation = &sample_image().annotations[0];
+        assert_eq!(annotation.bbox, rect(10, 10, 20, 10));
+        assert_eq!(annotation.polygon.len(), 4);
+        assert_eq!(annotation.to_contour().points[2], Point::new(30, 20));
+
+        let empty: Contour<i32> = Contour::new(Vec::new(), BorderType::Outer, None);
+        assert!(Annotation::from_contour(&empty, 0).is_none());
+
+        let wide = Contour::new(
+            vec![Point::new(i32::MIN, -5), Point::new(i32::MAX, 5)],
+            BorderType::Outer,
+            None,
+        );
+        let annotation = Annotation::from_contour(&wide, 0).unwrap();
+        assert_eq!(annotation.bbox, rect(0, 0, i32::MAX as u32, 5));
+    }
+
+    #[test]
+    fn test_coco_round_trip() {
+        let images = vec![sample_image()];
+        let categories = vec![
+            Category {
+                id: 0,
+                name: "label".to_string(),
+            },
+            Category {
+                id: 1,
+                name: "specimen".to_string(),
+            },
+        ];
+
+        let mut buffer = Vec::new();
+        write_coco(&mut buffer, &images, &categories).unwrap();
+
+        let document: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(document["annotations"][0]["bbox"], json!([10, 10, 20, 10]));
+        assert_eq!(document["annotations"][0]["area"], json!(200.0));
+        assert_eq!(document["annotations"][1]["segmentation"], json!([]));
+
+        let (read_images, read_categories) = read_coco(buffer.as_slice()).unwrap();
+        assert_eq!(read_images, images);
+        assert_eq!(read_categories, categories);
+    }
+
+    #[test]
+    fn test_read_coco_rejects_unknown_image() {
+        let document = r#"{
+            "images": [],
+            "annotations": [{"image_id": 3, "category_id": 1, "bbox": [0, 0, 1, 1]}],
+            "categories": []
+        }"#;
+        let error = read_coco(document.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_read_coco_rejects_out_of_range_values() {
+        let document = r#"{
+            "images": [{"id": 1, "file_name": "a.png", "width": 8589934592, "height": 1}],
+            "annotations": [],
+            "categories": []
+        }"#;
+        let error = read_coco(document.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_yolo_round_trip() {
+        let image = sample_image();
+
+        let mut boxes = Vec::new();
+        write_yolo(&mut boxes, &image.annotations, 100, 50, YoloFormat::Boxes).unwrap();
+        assert_eq!(
+            String::from_utf8(boxes.clone()).unwrap().lines().next(),
+            Some("1 0.200000 0.300000 0.200000 0.200000")
+        );
+        let read = read_yolo(boxes.as_slice(), 100, 50).unwrap();
+        assert_eq!(read.len(), 2);
+        for (read, written) in read.iter().zip(&image.annotations) {
+            assert_eq!(read.class_id, written.class_id);
+            assert_eq!(read.bbox, written.bbox);
+            assert!(read.polygon.is_empty());
+        }
+
+        let mut polygons = Vec::new();
+        write_yolo(
+            &mut polygons,
+            &image.annotations,
+            100,
+            50,
+            YoloFormat::Polygons,
+        )
+        .unwrap();
+        let read = read_yolo(polygons.as_slice(), 100, 50).unwrap();
+        assert_eq!(read[0], image.annotations[0]);
+        assert_eq!(read[1].bbox, image.annotations[1].bbox);
+        assert_eq!(read[1].polygon.len(), 4);
+    }
+
+    #[test]
+    fn test_read_yolo_rejects_malformed_lines() {
+        assert!(read_yolo("0 0.5 0.5\n".as_bytes(), 10, 10).is_err());
+        assert!(read_yolo("a 0.5 0.5 0.1 0.1\n".as_bytes(), 10, 10).is_err());
+        assert!(read_yolo("\n\n".as_bytes(), 10, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_voc_round_trip() {
+        let image = sample_image();
+        let class_names = ["label", "specimen"];
+
+        let mut buffer = Vec::new();
+        write_voc(&mut buffer, &image, &class_names).unwrap();
+        let xml = String::from_utf8(buffer.clone()).unwrap();
+        assert!(xml.contains("<filename>plate &amp; wells.png</filename>"));
+        assert!(xml.contains("<xmax>30</xmax>"));
+
+        let read = read_voc(buffer.as_slice(), &class_names).unwrap();
+        assert_eq!(read.file_name, image.file_name);
+        assert_eq!((read.width, read.height), (100, 50));
+        let boxes: Vec<(u32, Rect)> = read
+            .annotations
+            .iter()
+            .map(|a| (a.class_id, a.bbox))
+            .collect();
+        let expected: Vec<(u32, Rect)> = image
+            .annotations
+            .iter()
+            .map(|a| (a.class_id, a.bbox))
+            .collect();
+        assert_eq!(boxes, expected);
+
+        assert_eq!(
+            read_voc(buffer.as_slice(), &["label"]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        for size in ["-1", "NaN", "12.5", "1e20"] {
+            let xml = xml.replace("<width>100</width>", &format!("<width>{size}</width>"));
+            assert_eq!(
+                read_voc(xml.as_bytes(), &class_names).unwrap_err().kind(),
+                io::ErrorKind::InvalidData,
+                "{size}"
+            );
+        }
+        assert_eq!(
+            write_voc(Vec::new(), &image, &["label"])
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
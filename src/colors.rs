@@ -21,6 +21,20 @@ pub(crate) fn generate_contrasting_colors(n: usize, alpha: u8) -> Vec<Rgba<u8>>
     colors
 }
 
+/// Derives a color from an id alone, so the same id always gets the same color no matter
+/// how many other ids are colored.
+///
+/// Hues step around the color wheel by the golden angle, which keeps consecutive ids far
+/// apart.
+pub(crate) fn color_for_id(id: u64, alpha: u8) -> Rgba<u8> {
+    const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+    let hue = ((id as f64 * GOLDEN_RATIO_CONJUGATE).fract() * 360.0) as f32;
+
+    let hsl_color = Hsl::new(hue, 0.9, 0.5);
+    let srgb_u8: Srgb<u8> = Srgb::from_color(hsl_color).into_format();
+    Rgba([srgb_u8.red, srgb_u8.green, srgb_u8.blue, alpha])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -45,4 +59,12 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn color_for_id_is_stable_and_distinct() {
+        assert_eq!(color_for_id(0, 255), Rgba([242, 13, 13, 255]));
+        assert_eq!(color_for_id(7, 128), color_for_id(7, 128));
+        assert_ne!(color_for_id(1, 255), color_for_id(2, 255));
+        assert_eq!(color_for_id(3, 10)[3], 10);
+    }
 }
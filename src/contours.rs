@@ -1,10 +1,19 @@
+use crate::{
+    colors::generate_contrasting_colors,
+    drawing::draw_closed_polyline_mut,
+    geometry::{cross, segment_intersection},
+    rect::{Extent, ReadingOrder, RotatedRect, reading_order_of_extents},
+};
+use image::{Rgba, RgbaImage};
 use imageproc::{
     contours::{BorderType, Contour},
-    geometry::min_area_rect,
+    drawing::draw_cross_mut,
+    geometry::{contour_area, oriented_contour_area},
     point::Point,
 };
 use num::{Num, NumCast};
 use num_traits::AsPrimitive;
+use std::{collections::HashMap, hash::Hash, ops::RangeBounds};
 
 /// Calculates the perimeter of each contour and sorts them in descending order.
 ///
@@ -78,7 +87,7 @@ where
 ///
 /// # Type Parameters
 ///
-/// * `T`: The numeric type of the point coordinates. Must implement `Num`, `Copy`, `AsPrimitive<f32>`,
+/// * `T`: The numeric type of the point coordinates. Must implement `Num`, `Copy`,
 ///   and be compatible with `min_area_rect`.
 ///
 /// # Panics
@@ -89,19 +98,13 @@ pub fn remove_hypotenuse_in_place<T>(
     max_aspect_ratio: f32,
     border_type: Option<BorderType>,
 ) where
-    T: Num + NumCast + Copy + PartialEq + Eq + Ord + AsPrimitive<f32>,
+    T: Num + NumCast + Copy + PartialEq + Eq + Ord,
 {
     assert!(
         max_aspect_ratio.is_finite() && max_aspect_ratio > 0.0,
         "max_aspect_ratio must be a positive finite number"
     );
 
-    let distance_squared = |p1: Point<T>, p2: Point<T>| -> f32 {
-        let dx: f32 = p1.x.as_() - p2.x.as_();
-        let dy: f32 = p1.y.as_() - p2.y.as_();
-        dx * dx + dy * dy
-    };
-
     contours.retain(|contour| {
         if let Some(required_type) = border_type
             && contour.border_type != required_type
@@ -113,22 +116,13 @@ pub fn remove_hypotenuse_in_place<T>(
             return false;
         }
 
-        let rect_points = min_area_rect(&contour.points);
+        let rect = RotatedRect::enclosing(&contour.points);
 
-        let side1_squared = distance_squared(rect_points[0], rect_points[1]);
-        let side2_squared = distance_squared(rect_points[1], rect_points[2]);
-
-        if side1_squared < 1e-6 || side2_squared < 1e-6 {
+        if rect.width < 1e-3 || rect.height < 1e-3 {
             return false;
         }
 
-        let aspect_ratio = if side1_squared > side2_squared {
-            (side1_squared / side2_squared).sqrt()
-        } else {
-            (side2_squared / side1_squared).sqrt()
-        };
-
-        aspect_ratio < max_aspect_ratio
+        rect.aspect_ratio() < max_aspect_ratio as f64
     });
 }
 
@@ -176,23 +170,791 @@ pub fn sort_by_direct_children_count_owned<T>(
         .map(|(i, contour)| (contour, child_counts[i]))
         .collect();
 
+    #[allow(clippy::unnecessary_sort_by)]
     result.sort_unstable_by(|a, b| b.1.cmp(&a.1));
 
     result
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Whether a contour sits at an even or odd depth in the contour hierarchy.
+///
+/// For a well-formed hierarchy from `find_contours`, even depths are object borders
+/// (top-level objects, objects nested inside holes, ...) and odd depths are hole borders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NestingParity {
+    /// Depth 0, 2, 4, ...
+    Even,
+    /// Depth 1, 3, 5, ...
+    Odd,
+}
 
-    fn assert_float_eq(a: f64, b: f64) {
-        assert!(
-            (a - b).abs() < 1e-9,
-            "Assertion failed: expected {}, got {}",
-            b,
-            a
+impl NestingParity {
+    /// Returns the parity of the given nesting depth.
+    pub fn from_depth(depth: usize) -> Self {
+        if depth.is_multiple_of(2) {
+            NestingParity::Even
+        } else {
+            NestingParity::Odd
+        }
+    }
+}
+
+/// Computes the nesting depth of every contour by following its `parent` chain.
+///
+/// Top-level contours (no parent) have depth 0, their direct children depth 1, and so on.
+/// Depths are memoized, so the whole hierarchy is resolved in O(N).
+///
+/// A `parent` index that is out of range is treated as if the contour had no parent.
+/// Should the hierarchy contain a cycle, the chain is cut where it first repeats, so the
+/// function always terminates.
+///
+/// # Returns
+///
+/// A `Vec<usize>` with one depth per input contour, in the same order.
+pub fn contour_depths<T>(contours: &[Contour<T>]) -> Vec<usize> {
+    let mut depths: Vec<Option<usize>> = vec![None; contours.len()];
+    let mut chain = Vec::new();
+    let mut on_chain = vec![false; contours.len()];
+
+    for start in 0..contours.len() {
+        if depths[start].is_some() {
+            continue;
+        }
+
+        // Walk up until we reach a root, a resolved ancestor or a repeated index.
+        chain.clear();
+        let mut current = Some(start);
+        let mut base = 0;
+        while let Some(index) = current {
+            if let Some(depth) = depths[index] {
+                base = depth + 1;
+                break;
+            }
+            if on_chain[index] {
+                break;
+            }
+            on_chain[index] = true;
+            chain.push(index);
+            current = contours[index].parent.filter(|&p| p < contours.len());
+        }
+
+        for (offset, &index) in chain.iter().rev().enumerate() {
+            depths[index] = Some(base + offset);
+            on_chain[index] = false;
+        }
+    }
+
+    depths.into_iter().map(|d| d.unwrap_or(0)).collect()
+}
+
+/// Classifies every contour as even or odd nesting depth.
+///
+/// This is [`contour_depths`] reduced to parity, which is a more robust object/hole
+/// classification than `border_type` once contours have been filtered or merged.
+pub fn nesting_parities<T>(contours: &[Contour<T>]) -> Vec<NestingParity> {
+    contour_depths(contours)
+        .into_iter()
+        .map(NestingParity::from_depth)
+        .collect()
+}
+
+/// Keeps only the contours whose nesting depth lies within `depths`.
+///
+/// Depths are computed with [`contour_depths`] before anything is removed. Typical uses:
+///
+/// * `0..=0` keeps top-level objects only.
+/// * `1..=1` keeps holes directly inside top-level objects.
+/// * `2..=2` keeps objects nested inside those holes.
+///
+/// Unlike a plain `retain`, the `parent` indices of the surviving contours are rewritten to
+/// point at their nearest surviving ancestor (or `None`), so the hierarchy stays valid.
+/// Ancestors are memoized, so this is O(N) even for deeply nested hierarchies.
+pub fn filter_by_depth_in_place<T>(
+    contours: &mut Vec<Contour<T>>,
+    depths: impl RangeBounds<usize>,
+) {
+    let keep: Vec<bool> = contour_depths(contours)
+        .into_iter()
+        .map(|depth| depths.contains(&depth))
+        .collect();
+    retain_with_hierarchy(contours, &keep);
+}
+
+/// Keeps only the contours whose nesting depth has the given parity.
+///
+/// `parent` indices are rewritten the same way as in [`filter_by_depth_in_place`].
+pub fn filter_by_parity_in_place<T>(contours: &mut Vec<Contour<T>>, parity: NestingParity) {
+    let keep: Vec<bool> = nesting_parities(contours)
+        .into_iter()
+        .map(|p| p == parity)
+        .collect();
+    retain_with_hierarchy(contours, &keep);
+}
+
+/// Draws every contour onto `canvas`, colored by its nesting depth.
+///
+/// Each depth gets its own contrasting color (depth 0 is always the first color of the
+/// palette), which makes mis-nested contours from a bad threshold stand out immediately.
+///
+/// # Returns
+///
+/// The colors used, indexed by depth, so callers can draw a legend.
+pub fn draw_contours_by_depth_mut<T>(
+    canvas: &mut RgbaImage,
+    contours: &[Contour<T>],
+) -> Vec<Rgba<u8>>
+where
+    T: Copy + AsPrimitive<f32>,
+{
+    let depths = contour_depths(contours);
+    let level_count = depths.iter().max().map_or(0, |max| max + 1);
+    let colors = generate_contrasting_colors(level_count, 255);
+
+    for (contour, depth) in contours.iter().zip(depths) {
+        draw_closed_polyline_mut(canvas, &contour.points, colors[depth]);
+    }
+
+    colors
+}
+
+/// Why [`clean_contours_in_place`] removed a contour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalReason {
+    /// The contour had fewer points than the requested minimum.
+    TooFewPoints,
+    /// The contour encloses no area, e.g. a one-pixel-wide line traced forth and back.
+    ZeroArea,
+    /// The contour traces the same pixels as the contour at the given (original) index,
+    /// which was kept instead.
+    DuplicateOf(usize),
+}
+
+/// Summary of a [`clean_contours_in_place`] pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// The original index of every removed contour, with the reason it was removed.
+    pub removed: Vec<(usize, RemovalReason)>,
+    /// The original index of every surviving contour, in output order.
+    pub kept: Vec<usize>,
+}
+
+/// Removes degenerate and duplicate contours and repairs the hierarchy afterwards.
+///
+/// A contour is removed, in this order of precedence, when:
+///
+/// 1.  It has fewer than `min_points` points ([`RemovalReason::TooFewPoints`]).
+///     `remove_hypotenuse_in_place` silently drops contours with fewer than 4 points,
+///     so `4` reproduces that behavior while reporting it.
+/// 2.  Its shoelace area is zero ([`RemovalReason::ZeroArea`]).
+/// 3.  It traces pixels that are already covered by another contour
+///     ([`RemovalReason::DuplicateOf`]). This catches both exact duplicates (same pixels,
+///     any start point or direction) and the outer/hole pairs that `find_contours` emits
+///     for one-pixel-thick structures, where every pixel of the hole also lies on its
+///     parent's outer border. In that case the outer border is kept.
+///
+/// `parent` indices of the survivors are rewritten to their nearest surviving ancestor.
+/// Note that this means children of a removed duplicate hole are re-attached to the outer
+/// border that absorbed it.
+///
+/// # Returns
+///
+/// A [`CleanupReport`] listing what was removed and why, using indices into the input.
+pub fn clean_contours_in_place<T>(
+    contours: &mut Vec<Contour<T>>,
+    min_points: usize,
+) -> CleanupReport
+where
+    T: Num + NumCast + Copy + Ord + Hash,
+{
+    let pixel_sets: Vec<Vec<(T, T)>> = contours
+        .iter()
+        .map(|contour| {
+            let mut pixels: Vec<(T, T)> = contour.points.iter().map(|p| (p.x, p.y)).collect();
+            pixels.sort_unstable();
+            pixels.dedup();
+            pixels
+        })
+        .collect();
+
+    let mut reasons: Vec<Option<RemovalReason>> = vec![None; contours.len()];
+    let mut first_seen: HashMap<&[(T, T)], usize> = HashMap::new();
+
+    for (i, contour) in contours.iter().enumerate() {
+        if contour.points.len() < min_points {
+            reasons[i] = Some(RemovalReason::TooFewPoints);
+            continue;
+        }
+        if contour_area(&contour.points) == 0.0 {
+            reasons[i] = Some(RemovalReason::ZeroArea);
+            continue;
+        }
+        if let Some(&original) = first_seen.get(pixel_sets[i].as_slice()) {
+            reasons[i] = Some(RemovalReason::DuplicateOf(original));
+            continue;
+        }
+        first_seen.insert(&pixel_sets[i], i);
+    }
+
+    for (i, contour) in contours.iter().enumerate() {
+        if reasons[i].is_some() || contour.border_type != BorderType::Hole {
+            continue;
+        }
+        let Some(parent) = contour.parent.filter(|&p| p < contours.len()) else {
+            continue;
+        };
+        if reasons[parent].is_some() {
+            continue;
+        }
+        let parent_pixels = &pixel_sets[parent];
+        if pixel_sets[i]
+            .iter()
+            .all(|pixel| parent_pixels.binary_search(pixel).is_ok())
+        {
+            reasons[i] = Some(RemovalReason::DuplicateOf(parent));
+        }
+    }
+
+    let keep: Vec<bool> = reasons.iter().map(Option::is_none).collect();
+    retain_with_hierarchy(contours, &keep);
+
+    let mut report = CleanupReport::default();
+    for (i, reason) in reasons.into_iter().enumerate() {
+        match reason {
+            Some(reason) => report.removed.push((i, reason)),
+            None => report.kept.push(i),
+        }
+    }
+    report
+}
+
+/// Sorts contours in reading order by their axis-aligned extents.
+///
+/// This is [`rect::reading_order`](crate::rect::reading_order) applied to the bounding box
+/// of each contour's points, so the same row clustering and `min_overlap` semantics apply.
+/// The bounding box is computed directly from the points, without `min_area_rect`.
+///
+/// Since reordering invalidates `parent` indices, each contour is returned together with
+/// its index in the input vector. Contours without points are treated as sitting at the
+/// origin.
+///
+/// # Returns
+///
+/// A `Vec<(Contour<T>, usize)>` in reading order, where the `usize` is the original index.
+///
+/// # Panics
+///
+/// Panics if `min_overlap` is not in `(0, 1]`.
+pub fn sort_by_reading_order_owned<T>(
+    contours: Vec<Contour<T>>,
+    order: ReadingOrder,
+    min_overlap: f32,
+) -> Vec<(Contour<T>, usize)>
+where
+    T: Copy + AsPrimitive<f64>,
+{
+    let extents: Vec<Extent> = contours
+        .iter()
+        .map(|contour| {
+            let mut points = contour.points.iter().map(|p| (p.x.as_(), p.y.as_()));
+            let Some((x, y)) = points.next() else {
+                return Extent {
+                    min_x: 0.0,
+                    min_y: 0.0,
+                    max_x: 0.0,
+                    max_y: 0.0,
+                };
+            };
+            points.fold(
+                Extent {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                },
+                |e, (x, y)| Extent {
+                    min_x: e.min_x.min(x),
+                    min_y: e.min_y.min(y),
+                    max_x: e.max_x.max(x),
+                    max_y: e.max_y.max(y),
+                },
+            )
+        })
+        .collect();
+
+    let permutation = reading_order_of_extents(&extents, order, min_overlap);
+
+    let mut slots: Vec<Option<Contour<T>>> = contours.into_iter().map(Some).collect();
+    permutation
+        .into_iter()
+        .map(|i| {
+            (
+                slots[i].take().expect("permutation visits each index once"),
+                i,
+            )
+        })
+        .collect()
+}
+
+/// The direction in which a contour's points run, as displayed (y axis pointing down).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Winding {
+    /// Clockwise on screen. `find_contours` traces hole borders this way.
+    Clockwise,
+    /// Counter-clockwise on screen. `find_contours` traces outer borders this way.
+    CounterClockwise,
+}
+
+impl Winding {
+    /// Returns the opposite direction.
+    pub fn reversed(self) -> Self {
+        match self {
+            Winding::Clockwise => Winding::CounterClockwise,
+            Winding::CounterClockwise => Winding::Clockwise,
+        }
+    }
+}
+
+/// Determines the winding of a closed ring of points from the sign of its area.
+///
+/// Returns `None` when the ring encloses no area (fewer than 3 points, or all collinear).
+pub fn contour_winding<T>(points: &[Point<T>]) -> Option<Winding>
+where
+    T: Copy + AsPrimitive<f64>,
+{
+    let area = signed_area(points);
+    if area > 0.0 {
+        Some(Winding::Clockwise)
+    } else if area < 0.0 {
+        Some(Winding::CounterClockwise)
+    } else {
+        None
+    }
+}
+
+/// Calculates the signed area of a closed ring of points with the shoelace formula.
+///
+/// The result is positive for [`Winding::Clockwise`] rings and negative for
+/// [`Winding::CounterClockwise`] rings, as displayed with the y axis pointing down.
+/// This is `imageproc::geometry::oriented_contour_area` for any coordinate type, with the
+/// sign spelled out. Rings with fewer than 3 points have an area of `0.0`.
+pub fn signed_area<T>(points: &[Point<T>]) -> f64
+where
+    T: Copy + AsPrimitive<f64>,
+{
+    let points: Vec<Point<f64>> = points
+        .iter()
+        .map(|p| Point::new(p.x.as_(), p.y.as_()))
+        .collect();
+    oriented_contour_area(&points)
+}
+
+/// Reverses contours as needed so that outer borders wind as `outer_winding` and holes
+/// wind the opposite way.
+///
+/// `find_contours` already produces [`Winding::CounterClockwise`] outer borders, so pass
+/// [`Winding::Clockwise`] to get the reverse convention. Contours that enclose no area are
+/// left untouched.
+pub fn normalize_winding_in_place<T>(contours: &mut [Contour<T>], outer_winding: Winding)
+where
+    T: Copy + AsPrimitive<f64>,
+{
+    for contour in contours {
+        let expected = expected_winding(contour.border_type, outer_winding);
+        if contour_winding(&contour.points).is_some_and(|actual| actual != expected) {
+            contour.points.reverse();
+        }
+    }
+}
+
+/// Rotates a ring of points so that it starts at its top-left-most point.
+///
+/// The top-left-most point is the one with the smallest `y`, ties broken by the smallest
+/// `x`. If that point occurs more than once, the first occurrence is used. The order of
+/// the points along the ring is not changed.
+pub fn rotate_to_top_left_in_place<T>(points: &mut [Point<T>])
+where
+    T: Copy + PartialOrd,
+{
+    let mut start = 0;
+    for (i, p) in points.iter().enumerate().skip(1) {
+        let best = points[start];
+        if p.y < best.y || (p.y == best.y && p.x < best.x) {
+            start = i;
+        }
+    }
+    points.rotate_left(start);
+}
+
+/// Puts every contour into a canonical form: winding normalized with
+/// [`normalize_winding_in_place`], then rotated with [`rotate_to_top_left_in_place`].
+///
+/// Two contours that trace the same shape then have identical point lists, which keeps
+/// diffs and serialized output free of noise.
+pub fn normalize_contours_in_place<T>(contours: &mut [Contour<T>], outer_winding: Winding)
+where
+    T: Copy + PartialOrd + AsPrimitive<f64>,
+{
+    normalize_winding_in_place(contours, outer_winding);
+    for contour in contours {
+        rotate_to_top_left_in_place(&mut contour.points);
+    }
+}
+
+/// A problem found by [`validate_contour`].
+///
+/// Point indices refer to `contour.points`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContourIssue {
+    /// Fewer than 3 distinct points, so the contour cannot form a valid ring.
+    TooFewPoints,
+    /// `points[index]` is equal to the point after it (wrapping around at the end).
+    RepeatedPoint { index: usize },
+    /// The path doubles back on itself at `points[index]`.
+    Spike { index: usize },
+    /// The edges starting at `points[first_edge]` and `points[second_edge]` touch or cross
+    /// at `point`, even though they are not adjacent.
+    SelfIntersection {
+        first_edge: usize,
+        second_edge: usize,
+        point: (f64, f64),
+    },
+    /// The contour winds in the opposite direction to what its `border_type` requires.
+    WrongWinding { expected: Winding, actual: Winding },
+    /// `parent` does not refer to another contour in the set.
+    ParentOutOfRange { parent: usize },
+    /// The contour names itself as its own parent.
+    SelfParent,
+}
+
+impl ContourIssue {
+    /// Whether [`repair_contour`] can fix this issue without changing the shape.
+    ///
+    /// [`ContourIssue::SelfParent`] is not, since a single contour does not know its index.
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            ContourIssue::RepeatedPoint { .. }
+                | ContourIssue::Spike { .. }
+                | ContourIssue::WrongWinding { .. }
+                | ContourIssue::ParentOutOfRange { .. }
+        )
+    }
+}
+
+/// Checks a contour for the defects that polygon libraries typically reject.
+///
+/// The checks are:
+///
+/// * fewer than 3 distinct points,
+/// * repeated consecutive points,
+/// * spikes, where the path reverses direction,
+/// * self-intersections between non-adjacent edges, including edges that merely touch,
+/// * winding that does not match `border_type`: outer borders are expected to wind as
+///   `outer_winding` and holes the opposite way. Use [`Winding::CounterClockwise`] for the
+///   convention of `find_contours`.
+/// * a `parent` that is not in `0..contour_count`.
+///
+/// Spikes and self-intersections are detected after skipping repeated points.
+/// The self-intersection test compares every pair of edges, so it is O(N²) in the number
+/// of points.
+///
+/// # Arguments
+///
+/// * `contour` - The contour to check.
+/// * `contour_count` - The number of contours in the set `contour` belongs to.
+/// * `outer_winding` - The expected winding of outer borders.
+///
+/// # Returns
+///
+/// All issues found, or an empty `Vec` if the contour is valid.
+pub fn validate_contour<T>(
+    contour: &Contour<T>,
+    contour_count: usize,
+    outer_winding: Winding,
+) -> Vec<ContourIssue>
+where
+    T: Copy + PartialEq + AsPrimitive<f64>,
+{
+    let mut issues = Vec::new();
+    let points = &contour.points;
+    let n = points.len();
+
+    if let Some(parent) = contour.parent
+        && parent >= contour_count
+    {
+        issues.push(ContourIssue::ParentOutOfRange { parent });
+    }
+
+    for index in 0..n {
+        if n > 1 && points[index] == points[(index + 1) % n] {
+            issues.push(ContourIssue::RepeatedPoint { index });
+        }
+    }
+
+    // Work on the ring without repeated points, remembering the original indices.
+    let ring: Vec<(usize, (f64, f64))> = (0..n)
+        .filter(|&i| n == 1 || points[i] != points[(i + 1) % n])
+        .map(|i| (i, (points[i].x.as_(), points[i].y.as_())))
+        .collect();
+    let m = ring.len();
+
+    let mut distinct: Vec<(f64, f64)> = ring.iter().map(|&(_, p)| p).collect();
+    distinct.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    distinct.dedup();
+    if distinct.len() < 3 {
+        issues.push(ContourIssue::TooFewPoints);
+        return issues;
+    }
+
+    for k in 0..m {
+        let previous = ring[(k + m - 1) % m].1;
+        let (index, current) = ring[k];
+        let next = ring[(k + 1) % m].1;
+        let forward = (current.0 - previous.0, current.1 - previous.1);
+        let onward = (next.0 - current.0, next.1 - current.1);
+        if cross(previous, current, next) == 0.0
+            && forward.0 * onward.0 + forward.1 * onward.1 < 0.0
+        {
+            issues.push(ContourIssue::Spike { index });
+        }
+    }
+
+    for k in 0..m {
+        for l in (k + 2)..m {
+            if k == 0 && l == m - 1 {
+                continue;
+            }
+            if let Some(point) = segment_intersection(
+                ring[k].1,
+                ring[(k + 1) % m].1,
+                ring[l].1,
+                ring[(l + 1) % m].1,
+            ) {
+                issues.push(ContourIssue::SelfIntersection {
+                    first_edge: ring[k].0,
+                    second_edge: ring[l].0,
+                    point,
+                });
+            }
+        }
+    }
+
+    let expected = expected_winding(contour.border_type, outer_winding);
+    if let Some(actual) = contour_winding(points)
+        && actual != expected
+    {
+        issues.push(ContourIssue::WrongWinding { expected, actual });
+    }
+
+    issues
+}
+
+/// Validates every contour of a set, see [`validate_contour`].
+///
+/// In addition, a contour that names itself as its own parent is reported as
+/// [`ContourIssue::SelfParent`].
+///
+/// # Returns
+///
+/// One `Vec<ContourIssue>` per input contour, in the same order.
+pub fn validate_contours<T>(
+    contours: &[Contour<T>],
+    outer_winding: Winding,
+) -> Vec<Vec<ContourIssue>>
+where
+    T: Copy + PartialEq + AsPrimitive<f64>,
+{
+    contours
+        .iter()
+        .enumerate()
+        .map(|(i, contour)| {
+            let mut issues = validate_contour(contour, contours.len(), outer_winding);
+            if contour.parent == Some(i) {
+                issues.insert(0, ContourIssue::SelfParent);
+            }
+            issues
+        })
+        .collect()
+}
+
+/// Fixes the issues of a contour that can be repaired without changing its shape.
+///
+/// Repeated consecutive points and spikes are removed in one linear pass that also removes
+/// the spikes exposed by earlier removals, the point order is reversed if the winding is wrong, and a parent
+/// that is out of range is cleared. Self-intersections are left alone since there is no
+/// shape-preserving fix for them.
+///
+/// # Returns
+///
+/// The issues that remain after the repair, as reported by [`validate_contour`].
+pub fn repair_contour<T>(
+    contour: &mut Contour<T>,
+    contour_count: usize,
+    outer_winding: Winding,
+) -> Vec<ContourIssue>
+where
+    T: Copy + PartialEq + AsPrimitive<f64>,
+{
+    if contour.parent.is_some_and(|parent| parent >= contour_count) {
+        contour.parent = None;
+    }
+
+    let removable = |previous: Point<T>, current: Point<T>, next: Point<T>| {
+        if current == next {
+            return true;
+        }
+        let (a, b, c) = (
+            (previous.x.as_(), previous.y.as_()),
+            (current.x.as_(), current.y.as_()),
+            (next.x.as_(), next.y.as_()),
         );
+        cross(a, b, c) == 0.0 && (b.0 - a.0) * (c.0 - b.0) + (b.1 - a.1) * (c.1 - b.1) < 0.0
+    };
+
+    // Every point is pushed and popped at most once, and popping re-checks the point that
+    // becomes adjacent to the next one, so nested spikes collapse in the same pass.
+    let mut kept: Vec<Point<T>> = Vec::with_capacity(contour.points.len());
+    for &p in &contour.points {
+        while kept.len() >= 2 && removable(kept[kept.len() - 2], kept[kept.len() - 1], p) {
+            kept.pop();
+        }
+        if kept.last() != Some(&p) {
+            kept.push(p);
+        }
+    }
+    // The seam between the last and the first point has not been checked yet.
+    let mut start = 0;
+    while kept.len() - start >= 3 {
+        let end = kept.len();
+        if removable(kept[end - 2], kept[end - 1], kept[start]) {
+            kept.pop();
+        } else if removable(kept[end - 1], kept[start], kept[start + 1]) {
+            start += 1;
+        } else {
+            break;
+        }
+    }
+    kept.drain(..start);
+    if kept.len() == 2 && kept[0] == kept[1] {
+        kept.pop();
+    }
+    contour.points = kept;
+
+    let expected = expected_winding(contour.border_type, outer_winding);
+    if contour_winding(&contour.points).is_some_and(|actual| actual != expected) {
+        contour.points.reverse();
+    }
+
+    validate_contour(contour, contour_count, outer_winding)
+}
+
+/// Marks the issues of a contour on `canvas`.
+///
+/// Point issues (repeated points, spikes and self-intersections) are drawn as crosses at
+/// their location. Issues that concern the whole contour (too few points, wrong winding and
+/// invalid parents) are drawn by outlining the contour.
+pub fn draw_contour_issues_mut<T>(
+    canvas: &mut RgbaImage,
+    contour: &Contour<T>,
+    issues: &[ContourIssue],
+    color: Rgba<u8>,
+) where
+    T: Copy + AsPrimitive<f32>,
+{
+    let mut outlined = false;
+    for issue in issues {
+        let marker = match *issue {
+            // Indices from another contour's issues may be out of range.
+            ContourIssue::RepeatedPoint { index } | ContourIssue::Spike { index } => {
+                let Some(p) = contour.points.get(index) else {
+                    continue;
+                };
+                Some((p.x.as_(), p.y.as_()))
+            }
+            ContourIssue::SelfIntersection { point, .. } => Some((point.0 as f32, point.1 as f32)),
+            ContourIssue::TooFewPoints
+            | ContourIssue::WrongWinding { .. }
+            | ContourIssue::ParentOutOfRange { .. }
+            | ContourIssue::SelfParent => None,
+        };
+        match marker {
+            Some((x, y)) => draw_cross_mut(canvas, color, x.round() as i32, y.round() as i32),
+            None if !outlined => {
+                draw_closed_polyline_mut(canvas, &contour.points, color);
+                outlined = true;
+            }
+            None => {}
+        }
+    }
+}
+
+fn expected_winding(border_type: BorderType, outer_winding: Winding) -> Winding {
+    match border_type {
+        BorderType::Outer => outer_winding,
+        BorderType::Hole => outer_winding.reversed(),
+    }
+}
+
+/// Retains the contours flagged in `keep`, remapping `parent` indices to the nearest
+/// retained ancestor so the hierarchy survives the removal.
+fn retain_with_hierarchy<T>(contours: &mut Vec<Contour<T>>, keep: &[bool]) {
+    let len = contours.len();
+    let mut new_index = vec![None; len];
+    let mut next = 0;
+    for (i, &k) in keep.iter().enumerate() {
+        if k {
+            new_index[i] = Some(next);
+            next += 1;
+        }
+    }
+
+    // The nearest retained ancestor of every contour, memoized along each walk so that
+    // every contour is visited once. A cycle of removed contours leads to no ancestor.
+    let parent_of = |i: usize| contours[i].parent.filter(|&p| p < len);
+    let mut ancestors: Vec<Option<Option<usize>>> = vec![None; len];
+    let mut on_chain = vec![false; len];
+    let mut chain = Vec::new();
+    for start in 0..len {
+        chain.clear();
+        let mut current = start;
+        let ancestor = loop {
+            if let Some(ancestor) = ancestors[current] {
+                break ancestor;
+            }
+            on_chain[current] = true;
+            chain.push(current);
+            match parent_of(current) {
+                Some(p) if keep[p] => break Some(p),
+                Some(p) if !on_chain[p] => current = p,
+                _ => break None,
+            }
+        };
+        for &index in &chain {
+            ancestors[index] = Some(ancestor);
+            on_chain[index] = false;
+        }
     }
+    let new_parents: Vec<Option<usize>> = ancestors
+        .into_iter()
+        .map(|ancestor| ancestor.flatten().and_then(|p| new_index[p]))
+        .collect();
+
+    let mut flags = keep.iter();
+    let mut parents = new_parents.into_iter();
+    contours.retain_mut(|contour| {
+        let parent = parents.next().flatten();
+        let retained = *flags.next().unwrap_or(&false);
+        if retained {
+            contour.parent = parent;
+        }
+        retained
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_float_eq;
 
     fn make_contour(parent: Option<usize>, points: Vec<Point<i32>>) -> Contour<i32> {
         Contour {
@@ -415,4 +1177,481 @@ mod tests {
         assert!(last_two_point_counts.contains(&0));
         assert!(last_two_point_counts.contains(&1));
     }
+
+    #[test]
+    fn test_contour_depths_and_parities() {
+        // Hierarchy: 0 -> 1 -> 2 -> 3, 4 is a separate top-level contour,
+        // 5 has an out-of-range parent and is treated as top-level.
+        let contours = vec![
+            make_simple_contour(None),
+            make_simple_contour(Some(0)),
+            make_simple_contour(Some(1)),
+            make_simple_contour(Some(2)),
+            make_simple_contour(None),
+            make_simple_contour(Some(42)),
+        ];
+        assert_eq!(contour_depths(&contours), vec![0, 1, 2, 3, 0, 0]);
+        assert_eq!(
+            nesting_parities(&contours),
+            vec![
+                NestingParity::Even,
+                NestingParity::Odd,
+                NestingParity::Even,
+                NestingParity::Odd,
+                NestingParity::Even,
+                NestingParity::Even,
+            ]
+        );
+
+        // A malformed, cyclic hierarchy must not hang.
+        let cyclic = vec![make_simple_contour(Some(1)), make_simple_contour(Some(0))];
+        assert_eq!(contour_depths(&cyclic).len(), 2);
+    }
+
+    #[test]
+    fn test_filter_by_depth_remaps_parents() {
+        let contours = vec![
+            make_contour(None, vec![Point::new(0, 0)]), // 0, depth 0
+            make_contour(Some(0), vec![Point::new(1, 1)]), // 1, depth 1
+            make_contour(Some(1), vec![Point::new(2, 2)]), // 2, depth 2
+            make_contour(Some(2), vec![Point::new(3, 3)]), // 3, depth 3
+            make_contour(None, vec![Point::new(9, 9)]), // 4, depth 0
+        ];
+
+        let mut top_level = contours.clone();
+        filter_by_depth_in_place(&mut top_level, 0..=0);
+        assert_eq!(top_level.len(), 2);
+        assert!(top_level.iter().all(|c| c.parent.is_none()));
+
+        // Keeping depths 0 and 2 makes contour 2 a direct child of contour 0.
+        let mut objects = contours.clone();
+        filter_by_parity_in_place(&mut objects, NestingParity::Even);
+        let points: Vec<_> = objects.iter().map(|c| c.points[0]).collect();
+        assert_eq!(
+            points,
+            vec![Point::new(0, 0), Point::new(2, 2), Point::new(9, 9)]
+        );
+        assert_eq!(objects[1].parent, Some(0));
+
+        // Dropping the whole top level leaves orphans without a parent.
+        let mut nested = contours.clone();
+        filter_by_depth_in_place(&mut nested, 2..);
+        assert_eq!(nested.len(), 2);
+        assert_eq!(nested[0].parent, None);
+        assert_eq!(nested[1].parent, Some(0));
+
+        // A deep chain, where walking every parent chain from scratch would be quadratic.
+        let chain: Vec<Contour<i32>> = (0..100_000usize)
+            .map(|i| make_contour(i.checked_sub(1), vec![Point::new(0, 0)]))
+            .collect();
+        assert_eq!(contour_depths(&chain)[99_999], 99_999);
+        let mut deep = chain.clone();
+        filter_by_depth_in_place(&mut deep, 0..=0);
+        assert_eq!(deep.len(), 1);
+        let mut even = chain;
+        filter_by_parity_in_place(&mut even, NestingParity::Even);
+        assert_eq!(even.len(), 50_000);
+        assert_eq!(even[0].parent, None);
+        assert!((1..even.len()).all(|i| even[i].parent == Some(i - 1)));
+
+        // A cycle of removed contours leaves its descendants without a parent.
+        let mut cyclic = vec![
+            make_contour(Some(1), vec![Point::new(0, 0)]),
+            make_contour(Some(0), vec![Point::new(1, 1)]),
+            make_contour(Some(1), vec![Point::new(2, 2)]),
+        ];
+        retain_with_hierarchy(&mut cyclic, &[false, false, true]);
+        assert_eq!(cyclic.len(), 1);
+        assert_eq!(cyclic[0].parent, None);
+    }
+
+    #[test]
+    fn test_draw_contours_by_depth() {
+        let outer = Contour::new(
+            vec![
+                Point::new(1, 1),
+                Point::new(8, 1),
+                Point::new(8, 8),
+                Point::new(1, 8),
+            ],
+            BorderType::Outer,
+            None,
+        );
+        let hole = Contour::new(
+            vec![
+                Point::new(3, 3),
+                Point::new(6, 3),
+                Point::new(6, 6),
+                Point::new(3, 6),
+            ],
+            BorderType::Hole,
+            Some(0),
+        );
+        let background = Rgba([0, 0, 0, 255]);
+        let mut canvas = RgbaImage::from_pixel(10, 10, background);
+
+        let colors = draw_contours_by_depth_mut(&mut canvas, &[outer, hole]);
+
+        assert_eq!(colors, generate_contrasting_colors(2, 255));
+        assert_eq!(*canvas.get_pixel(1, 1), colors[0]);
+        assert_eq!(*canvas.get_pixel(3, 3), colors[1]);
+        assert_eq!(*canvas.get_pixel(2, 2), background);
+    }
+
+    #[test]
+    fn test_clean_contours_in_place() {
+        let square = |offset: i32| {
+            vec![
+                Point::new(offset, offset),
+                Point::new(offset + 4, offset),
+                Point::new(offset + 4, offset + 4),
+                Point::new(offset, offset + 4),
+            ]
+        };
+
+        let mut contours = vec![
+            // 0: a valid outer border.
+            Contour::new(square(0), BorderType::Outer, None),
+            // 1: a single stray pixel.
+            Contour::new(vec![Point::new(20, 20)], BorderType::Outer, None),
+            // 2: a one-pixel-wide line traced forth and back.
+            Contour::new(
+                vec![
+                    Point::new(30, 0),
+                    Point::new(31, 0),
+                    Point::new(32, 0),
+                    Point::new(33, 0),
+                    Point::new(32, 0),
+                    Point::new(31, 0),
+                ],
+                BorderType::Outer,
+                None,
+            ),
+            // 3: the same pixels as 0, traced from a different start point.
+            Contour::new(
+                {
+                    let mut points = square(0);
+                    points.rotate_left(2);
+                    points
+                },
+                BorderType::Outer,
+                None,
+            ),
+            // 4: a hole lying entirely on the border of 0 (one-pixel-thick ring).
+            Contour::new(square(0)[1..].to_vec(), BorderType::Hole, Some(0)),
+            // 5: an object inside that hole.
+            Contour::new(square(1), BorderType::Outer, Some(4)),
+        ];
+
+        let report = clean_contours_in_place(&mut contours, 3);
+
+        assert_eq!(
+            report.removed,
+            vec![
+                (1, RemovalReason::TooFewPoints),
+                (2, RemovalReason::ZeroArea),
+                (3, RemovalReason::DuplicateOf(0)),
+                (4, RemovalReason::DuplicateOf(0)),
+            ]
+        );
+        assert_eq!(report.kept, vec![0, 5]);
+        assert_eq!(contours.len(), 2);
+        assert_eq!(contours[0].points, square(0));
+        // The object re-attaches to the outer border that absorbed its hole.
+        assert_eq!(contours[1].parent, Some(0));
+    }
+
+    #[test]
+    fn test_sort_by_reading_order_owned() {
+        let square_at = |x: i32, y: i32| {
+            make_contour(
+                None,
+                vec![
+                    Point::new(x, y),
+                    Point::new(x + 5, y),
+                    Point::new(x + 5, y + 5),
+                    Point::new(x, y + 5),
+                ],
+            )
+        };
+        let contours = vec![
+            square_at(10, 10),
+            square_at(0, 11),
+            square_at(10, 0),
+            square_at(0, 1),
+        ];
+
+        let sorted = sort_by_reading_order_owned(contours, ReadingOrder::RowMajor, 0.5);
+
+        let original_indices: Vec<usize> = sorted.iter().map(|(_, i)| *i).collect();
+        assert_eq!(original_indices, vec![3, 2, 1, 0]);
+        assert_eq!(sorted[0].0.points[0], Point::new(0, 1));
+    }
+
+    #[test]
+    fn test_validate_contour_accepts_find_contours_output() {
+        let mut image = image::GrayImage::new(10, 10);
+        for y in 2..7 {
+            for x in 2..7 {
+                image.put_pixel(x, y, image::Luma([255]));
+            }
+        }
+        image.put_pixel(4, 4, image::Luma([0]));
+        let contours: Vec<Contour<i32>> = imageproc::contours::find_contours(&image);
+        assert_eq!(contours.len(), 2);
+
+        let issues = validate_contours(&contours, Winding::CounterClockwise);
+        assert!(issues.iter().all(|i| i.is_empty()), "{issues:?}");
+
+        let issues = validate_contours(&contours, Winding::Clockwise);
+        assert_eq!(
+            issues[0],
+            vec![ContourIssue::WrongWinding {
+                expected: Winding::Clockwise,
+                actual: Winding::CounterClockwise,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_contour_finds_defects() {
+        // A bow-tie whose edges cross at (1, 1).
+        let bow_tie = Contour::new(
+            vec![
+                Point::new(0, 0),
+                Point::new(2, 2),
+                Point::new(2, 0),
+                Point::new(0, 2),
+            ],
+            BorderType::Outer,
+            Some(7),
+        );
+        let issues = validate_contour(&bow_tie, 3, Winding::CounterClockwise);
+        assert!(issues.contains(&ContourIssue::ParentOutOfRange { parent: 7 }));
+        assert!(issues.contains(&ContourIssue::SelfIntersection {
+            first_edge: 0,
+            second_edge: 2,
+            point: (1.0, 1.0),
+        }));
+
+        // A square with a repeated point and a spike sticking out of its top edge.
+        let spiky = Contour::new(
+            vec![
+                Point::new(0, 0),
+                Point::new(0, 4),
+                Point::new(4, 4),
+                Point::new(4, 0),
+                Point::new(4, 0),
+                Point::new(2, 0),
+                Point::new(2, -3),
+                Point::new(2, 0),
+            ],
+            BorderType::Outer,
+            None,
+        );
+        let issues = validate_contour(&spiky, 1, Winding::CounterClockwise);
+        assert!(issues.contains(&ContourIssue::RepeatedPoint { index: 3 }));
+        assert!(issues.contains(&ContourIssue::Spike { index: 6 }));
+
+        let degenerate = Contour::new(
+            vec![Point::new(0, 0), Point::new(1, 0), Point::new(0, 0)],
+            BorderType::Outer,
+            None,
+        );
+        assert!(
+            validate_contour(&degenerate, 1, Winding::CounterClockwise)
+                .contains(&ContourIssue::TooFewPoints)
+        );
+    }
+
+    #[test]
+    fn test_repair_contour() {
+        // A clockwise hole border that should be counter-clockwise, with a spike and
+        // a repeated point.
+        let mut contour = Contour::new(
+            vec![
+                Point::new(0, 0),
+                Point::new(0, 4),
+                Point::new(4, 4),
+                Point::new(4, 0),
+                Point::new(4, 0),
+                Point::new(2, 0),
+                Point::new(2, -3),
+                Point::new(2, 0),
+            ],
+            BorderType::Hole,
+            Some(5),
+        );
+        let remaining = repair_contour(&mut contour, 2, Winding::Clockwise);
+
+        assert!(remaining.is_empty(), "{remaining:?}");
+        assert_eq!(contour.parent, None);
+        assert_eq!(
+            contour_winding(&contour.points),
+            Some(Winding::CounterClockwise)
+        );
+        // Only the spike tip and the duplicates go; the collinear (2, 0) is a valid vertex.
+        assert_eq!(contour.points.len(), 5);
+
+        // Self-intersections are reported but not touched.
+        let mut bow_tie = Contour::new(
+            vec![
+                Point::new(0, 0),
+                Point::new(2, 2),
+                Point::new(2, 0),
+                Point::new(0, 2),
+            ],
+            BorderType::Outer,
+            None,
+        );
+        let remaining = repair_contour(&mut bow_tie, 1, Winding::CounterClockwise);
+        assert!(remaining.iter().all(|issue| !issue.is_repairable()));
+        assert_eq!(bow_tie.points.len(), 4);
+
+        // A long spike whose removal exposes the next one, across the start of the ring.
+        let mut nested = Contour::new(
+            vec![
+                Point::new(0, -2),
+                Point::new(0, -3),
+                Point::new(0, -2),
+                Point::new(0, -1),
+                Point::new(0, 0),
+                Point::new(0, 4),
+                Point::new(4, 4),
+                Point::new(4, 0),
+                Point::new(0, 0),
+                Point::new(0, -1),
+            ],
+            BorderType::Outer,
+            None,
+        );
+        let remaining = repair_contour(&mut nested, 1, Winding::CounterClockwise);
+        assert!(remaining.is_empty(), "{remaining:?}");
+        assert_eq!(
+            nested.points,
+            vec![
+                Point::new(0, 0),
+                Point::new(0, 4),
+                Point::new(4, 4),
+                Point::new(4, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_contours_reports_self_parent() {
+        let square = |parent| {
+            Contour::new(
+                vec![
+                    Point::new(0, 0),
+                    Point::new(0, 2),
+                    Point::new(2, 2),
+                    Point::new(2, 0),
+                ],
+                BorderType::Outer,
+                parent,
+            )
+        };
+        let issues = validate_contours(&[square(None), square(Some(1))], Winding::CounterClockwise);
+        assert!(issues[0].is_empty());
+        assert_eq!(issues[1], vec![ContourIssue::SelfParent]);
+        assert!(!ContourIssue::SelfParent.is_repairable());
+    }
+
+    #[test]
+    fn test_draw_contour_issues() {
+        let contour = Contour::new(
+            vec![
+                Point::new(2, 2),
+                Point::new(6, 6),
+                Point::new(6, 2),
+                Point::new(2, 6),
+            ],
+            BorderType::Outer,
+            None,
+        );
+        let issues = validate_contour(&contour, 1, Winding::CounterClockwise);
+        let background = Rgba([0, 0, 0, 255]);
+        let color = Rgba([255, 0, 255, 255]);
+        let mut canvas = RgbaImage::from_pixel(10, 10, background);
+
+        draw_contour_issues_mut(&mut canvas, &contour, &issues, color);
+
+        // The cross at the crossing point (4, 4).
+        assert_eq!(*canvas.get_pixel(4, 4), color);
+        assert_eq!(*canvas.get_pixel(4, 3), color);
+        assert_eq!(*canvas.get_pixel(3, 4), color);
+        assert_eq!(*canvas.get_pixel(0, 0), background);
+
+        // Point issues of another, longer contour are skipped instead of panicking.
+        let foreign = [ContourIssue::Spike { index: 40 }];
+        let mut untouched = RgbaImage::from_pixel(10, 10, background);
+        draw_contour_issues_mut(&mut untouched, &contour, &foreign, color);
+        assert!(untouched.pixels().all(|&p| p == background));
+    }
+
+    #[test]
+    fn test_signed_area_and_winding() {
+        let clockwise = vec![
+            Point::new(0, 0),
+            Point::new(4, 0),
+            Point::new(4, 3),
+            Point::new(0, 3),
+        ];
+        assert_float_eq(signed_area(&clockwise), 12.0);
+        assert_eq!(contour_winding(&clockwise), Some(Winding::Clockwise));
+        assert_float_eq(
+            signed_area(&clockwise),
+            imageproc::geometry::oriented_contour_area(&clockwise),
+        );
+
+        let mut counter_clockwise = clockwise.clone();
+        counter_clockwise.reverse();
+        assert_float_eq(signed_area(&counter_clockwise), -12.0);
+        assert_eq!(
+            contour_winding(&counter_clockwise),
+            Some(Winding::CounterClockwise)
+        );
+
+        assert_eq!(contour_winding(&clockwise[..2]), None);
+    }
+
+    #[test]
+    fn test_normalize_contours_in_place() {
+        let ring = vec![
+            Point::new(0, 0),
+            Point::new(4, 0),
+            Point::new(4, 4),
+            Point::new(0, 4),
+        ];
+
+        // The same clockwise square, traced from two different start points.
+        let mut shifted = ring.clone();
+        shifted.rotate_left(2);
+        let mut contours = vec![
+            Contour::new(ring.clone(), BorderType::Outer, None),
+            Contour::new(shifted, BorderType::Outer, None),
+            Contour::new(ring.clone(), BorderType::Hole, Some(0)),
+        ];
+
+        normalize_contours_in_place(&mut contours, Winding::CounterClockwise);
+
+        let expected_outer = vec![
+            Point::new(0, 0),
+            Point::new(0, 4),
+            Point::new(4, 4),
+            Point::new(4, 0),
+        ];
+        assert_eq!(contours[0].points, expected_outer);
+        assert_eq!(contours[1].points, expected_outer);
+        // Holes keep the opposite (clockwise) winding.
+        assert_eq!(contours[2].points, ring);
+
+        let mut points = vec![Point::new(3, 1), Point::new(1, 0), Point::new(0, 0)];
+        rotate_to_top_left_in_place(&mut points);
+        assert_eq!(
+            points,
+            vec![Point::new(0, 0), Point::new(3, 1), Point::new(1, 0)]
+        );
+    }
 }
@@ -0,0 +1,72 @@
+use image::{Rgba, RgbaImage};
+use imageproc::{drawing::draw_line_segment_mut, point::Point};
+use num_traits::AsPrimitive;
+
+/// Draws a closed polyline through `points`, joining the last point back to the first.
+///
+/// A single point is drawn as one pixel; an empty slice draws nothing.
+pub(crate) fn draw_closed_polyline_mut<T>(
+    canvas: &mut RgbaImage,
+    points: &[Point<T>],
+    color: Rgba<u8>,
+) where
+    T: Copy + AsPrimitive<f32>,
+{
+    for (p1, p2) in points.iter().zip(points.iter().cycle().skip(1)) {
+        draw_line_segment_mut(
+            canvas,
+            (p1.x.as_(), p1.y.as_()),
+            (p2.x.as_(), p2.y.as_()),
+            color,
+        );
+    }
+}
+
+/// 3x5 bitmap glyphs for the digits 0-9, one bit per cell, row-major from the top-left.
+const DIGIT_GLYPHS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+/// Draws `number` in a tiny built-in bitmap font with its top-left corner at `(x, y)`.
+///
+/// Each glyph cell is `scale` pixels wide, so a digit is `3 * scale` by `5 * scale` pixels
+/// with one cell of spacing. This avoids pulling in a font just to label debug output.
+/// Anything outside the canvas is clipped.
+pub(crate) fn draw_number_mut(
+    canvas: &mut RgbaImage,
+    (x, y): (i32, i32),
+    number: usize,
+    scale: u32,
+    color: Rgba<u8>,
+) {
+    let scale = scale.max(1) as i32;
+    let (width, height) = canvas.dimensions();
+
+    for (position, digit) in number.to_string().bytes().enumerate() {
+        let glyph = DIGIT_GLYPHS[(digit - b'0') as usize];
+        let origin_x = x + position as i32 * 4 * scale;
+        for cell in 0..15 {
+            if glyph & (1 << (14 - cell)) == 0 {
+                continue;
+            }
+            let cell_x = origin_x + (cell % 3) * scale;
+            let cell_y = y + (cell / 3) * scale;
+            for py in cell_y..cell_y + scale {
+                for px in cell_x..cell_x + scale {
+                    if px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+                        canvas.put_pixel(px as u32, py as u32, color);
+                    }
+                }
+            }
+        }
+    }
+}
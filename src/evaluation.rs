@@ -0,0 +1,448 @@
+use crate::{
+    annotations::Annotation,
+    rect::{Overlap, descending_score_order, draw_rect_outline_mut},
+};
+use image::{Rgba, RgbaImage, math::Rect};
+use std::collections::BTreeSet;
+
+/// A prediction matched to a ground-truth object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    /// The index of the prediction.
+    pub prediction: usize,
+    /// The index of the ground-truth object.
+    pub ground_truth: usize,
+    /// The IoU between the two.
+    pub iou: f64,
+}
+
+/// The outcome of matching predictions against ground truth with [`match_detections`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Matching {
+    /// Matched pairs, in descending prediction score order.
+    pub true_positives: Vec<Match>,
+    /// Indices of predictions without a ground-truth match, in descending score order.
+    pub false_positives: Vec<usize>,
+    /// Indices of ground-truth objects no prediction was matched to, in ascending order.
+    pub false_negatives: Vec<usize>,
+}
+
+impl Matching {
+    /// The fraction of predictions that are true positives, or 0 without predictions.
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives.len(),
+            self.true_positives.len() + self.false_positives.len(),
+        )
+    }
+
+    /// The fraction of ground-truth objects that were found, or 0 without ground truth.
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives.len(),
+            self.true_positives.len() + self.false_negatives.len(),
+        )
+    }
+
+    /// The harmonic mean of precision and recall.
+    pub fn f1(&self) -> f64 {
+        f1(self.precision(), self.recall())
+    }
+}
+
+/// One point of a precision-recall curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrecisionRecallPoint {
+    /// Keeping only predictions scoring at least this much gives the values below.
+    pub score: f64,
+    /// The precision at this score threshold.
+    pub precision: f64,
+    /// The recall at this score threshold.
+    pub recall: f64,
+    /// The F1 score at this score threshold.
+    pub f1: f64,
+}
+
+/// The predictions and ground truth of one image, for [`mean_average_precision`].
+#[derive(Debug, Clone, Copy)]
+pub struct ImageDetections<'a> {
+    /// The predicted objects. Only `class_id` and `bbox` are used.
+    pub predictions: &'a [Annotation],
+    /// One confidence score per prediction.
+    pub scores: &'a [f64],
+    /// The ground-truth objects, for example from `annotations::read_coco`.
+    pub ground_truth: &'a [Annotation],
+}
+
+/// Matches predictions to ground-truth objects at an IoU threshold.
+///
+/// Predictions are visited in descending score order, as in the COCO and Pascal VOC
+/// evaluations. Each one is matched to the unmatched ground-truth object it overlaps most,
+/// provided their IoU is at least `iou_threshold`; otherwise it is a false positive. Equal
+/// scores and equal IoUs are broken by the lower index, so the result is deterministic.
+///
+/// # Arguments
+///
+/// * `predictions` - The predicted boxes, either `Rect`s or `rect::RotatedRect`s.
+/// * `scores` - One confidence score per prediction.
+/// * `ground_truth` - The reference boxes.
+/// * `iou_threshold` - The minimum IoU for a match, typically 0.5.
+///
+/// # Panics
+///
+/// Panics if `predictions` and `scores` have different lengths.
+pub fn match_detections<B: Overlap>(
+    predictions: &[B],
+    scores: &[f64],
+    ground_truth: &[B],
+    iou_threshold: f64,
+) -> Matching {
+    assert_eq!(
+        predictions.len(),
+        scores.len(),
+        "predictions and scores must have the same length"
+    );
+
+    let mut matched = vec![false; ground_truth.len()];
+    let mut matching = Matching::default();
+
+    for prediction in descending_score_order(scores) {
+        let mut best: Option<(usize, f64)> = None;
+        for (index, truth) in ground_truth.iter().enumerate() {
+            if matched[index] {
+                continue;
+            }
+            let iou = predictions[prediction].iou(truth);
+            if iou >= iou_threshold && best.is_none_or(|(_, best_iou)| iou > best_iou) {
+                best = Some((index, iou));
+            }
+        }
+
+        match best {
+            Some((index, iou)) => {
+                matched[index] = true;
+                matching.true_positives.push(Match {
+                    prediction,
+                    ground_truth: index,
+                    iou,
+                });
+            }
+            None => matching.false_positives.push(prediction),
+        }
+    }
+
+    matching.false_negatives = (0..ground_truth.len()).filter(|&i| !matched[i]).collect();
+    matching
+}
+
+/// Computes the precision-recall curve of the predictions at an IoU threshold.
+///
+/// There is one point per distinct score, in descending score order, so the curve also
+/// serves as the F1-vs-score-threshold curve: the point with the highest `f1` gives the
+/// best operating threshold.
+///
+/// # Panics
+///
+/// Panics if `predictions` and `scores` have different lengths.
+pub fn precision_recall_curve<B: Overlap>(
+    predictions: &[B],
+    scores: &[f64],
+    ground_truth: &[B],
+    iou_threshold: f64,
+) -> Vec<PrecisionRecallPoint> {
+    let matching = match_detections(predictions, scores, ground_truth, iou_threshold);
+    curve(&ranked_outcomes(&matching, scores), ground_truth.len())
+}
+
+/// Computes the average precision of the predictions at an IoU threshold.
+///
+/// This is the area under the precision-recall curve after making precision monotonically
+/// decreasing (all-point interpolation, as in Pascal VOC 2010 and later).
+///
+/// # Returns
+///
+/// `None` if there is no ground truth, since AP is undefined then.
+///
+/// # Panics
+///
+/// Panics if `predictions` and `scores` have different lengths.
+pub fn average_precision<B: Overlap>(
+    predictions: &[B],
+    scores: &[f64],
+    ground_truth: &[B],
+    iou_threshold: f64,
+) -> Option<f64> {
+    let matching = match_detections(predictions, scores, ground_truth, iou_threshold);
+    interpolated_average_precision(&ranked_outcomes(&matching, scores), ground_truth.len())
+}
+
+/// Computes the mean average precision over classes and IoU thresholds.
+///
+/// For every class with ground truth and every threshold, predictions are matched per
+/// image and only against objects of the same class, then pooled over all images to
+/// compute one AP. The result is the mean of these APs: pass `&[0.5]` for the Pascal VOC
+/// metric, or `0.50, 0.55, ..., 0.95` for the COCO metric.
+///
+/// # Returns
+///
+/// `None` if no image has ground truth or `iou_thresholds` is empty.
+///
+/// # Panics
+///
+/// Panics if an image has a different number of predictions and scores.
+pub fn mean_average_precision(images: &[ImageDetections], iou_thresholds: &[f64]) -> Option<f64> {
+    let classes: BTreeSet<u32> = images
+        .iter()
+        .flat_map(|image| image.ground_truth.iter().map(|a| a.class_id))
+        .collect();
+
+    let mut sum = 0.0;
+    let mut count = 0usize;
+    for &class_id in &classes {
+        for &iou_threshold in iou_thresholds {
+            let mut outcomes = Vec::new();
+            let mut ground_truth_count = 0;
+
+            for image in images {
+                assert_eq!(
+                    image.predictions.len(),
+                    image.scores.len(),
+                    "predictions and scores must have the same length"
+                );
+                let (predictions, scores): (Vec<Rect>, Vec<f64>) = image
+                    .predictions
+                    .iter()
+                    .zip(image.scores)
+                    .filter(|(a, _)| a.class_id == class_id)
+                    .map(|(a, &score)| (a.bbox, score))
+                    .unzip();
+                let truth: Vec<Rect> = image
+                    .ground_truth
+                    .iter()
+                    .filter(|a| a.class_id == class_id)
+                    .map(|a| a.bbox)
+                    .collect();
+
+                let matching = match_detections(&predictions, &scores, &truth, iou_threshold);
+                outcomes.extend(ranked_outcomes(&matching, &scores));
+                ground_truth_count += truth.len();
+            }
+
+            outcomes.sort_by(|a, b| b.0.total_cmp(&a.0));
+            if let Some(ap) = interpolated_average_precision(&outcomes, ground_truth_count) {
+                sum += ap;
+                count += 1;
+            }
+        }
+    }
+
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Draws the result of [`match_detections`]: true positives in green, false positives in
+/// red and false negatives (missed ground truth) in blue.
+///
+/// Matched ground-truth boxes are not drawn, to keep the overlay readable.
+///
+/// # Arguments
+///
+/// * `canvas` - The image to draw on.
+/// * `predictions` - The predictions that were passed to [`match_detections`].
+/// * `ground_truth` - The ground truth that was passed to [`match_detections`].
+/// * `matching` - The result of [`match_detections`].
+pub fn draw_matching_mut(
+    canvas: &mut RgbaImage,
+    predictions: &[Rect],
+    ground_truth: &[Rect],
+    matching: &Matching,
+) {
+    const TRUE_POSITIVE: Rgba<u8> = Rgba([0, 255, 0, 255]);
+    const FALSE_POSITIVE: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const FALSE_NEGATIVE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    for &index in &matching.false_negatives {
+        draw_rect_outline_mut(canvas, &ground_truth[index], FALSE_NEGATIVE);
+    }
+    for m in &matching.true_positives {
+        draw_rect_outline_mut(canvas, &predictions[m.prediction], TRUE_POSITIVE);
+    }
+    for &index in &matching.false_positives {
+        draw_rect_outline_mut(canvas, &predictions[index], FALSE_POSITIVE);
+    }
+}
+
+/// `(score, is_true_positive)` for every prediction, in descending score order.
+fn ranked_outcomes(matching: &Matching, scores: &[f64]) -> Vec<(f64, bool)> {
+    let mut outcomes: Vec<(f64, bool)> = matching
+        .true_positives
+        .iter()
+        .map(|m| (scores[m.prediction], true))
+        .chain(matching.false_positives.iter().map(|&i| (scores[i], false)))
+        .collect();
+    outcomes.sort_by(|a, b| b.0.total_cmp(&a.0));
+    outcomes
+}
+
+fn curve(outcomes: &[(f64, bool)], ground_truth_count: usize) -> Vec<PrecisionRecallPoint> {
+    let mut points: Vec<PrecisionRecallPoint> = Vec::new();
+    let mut true_positives = 0;
+
+    for (rank, &(score, is_true_positive)) in outcomes.iter().enumerate() {
+        if is_true_positive {
+            true_positives += 1;
+        }
+        let precision = ratio(true_positives, rank + 1);
+        let recall = ratio(true_positives, ground_truth_count);
+        let point = PrecisionRecallPoint {
+            score,
+            precision,
+            recall,
+            f1: f1(precision, recall),
+        };
+        // Predictions with equal scores cannot be separated by a threshold.
+        match points.last_mut() {
+            Some(last) if last.score == score => *last = point,
+            _ => points.push(point),
+        }
+    }
+
+    points
+}
+
+fn interpolated_average_precision(
+    outcomes: &[(f64, bool)],
+    ground_truth_count: usize,
+) -> Option<f64> {
+    if ground_truth_count == 0 {
+        return None;
+    }
+
+    let points = curve(outcomes, ground_truth_count);
+    // The interpolated precision at each point is the best precision at any higher recall.
+    let mut envelope: Vec<f64> = points.iter().map(|p| p.precision).collect();
+    for i in (0..envelope.len().saturating_sub(1)).rev() {
+        envelope[i] = envelope[i].max(envelope[i + 1]);
+    }
+
+    let mut average_precision = 0.0;
+    let mut previous_recall = 0.0;
+    for (point, precision) in points.iter().zip(envelope) {
+        average_precision += (point.recall - previous_recall) * precision;
+        previous_recall = point.recall;
+    }
+    Some(average_precision)
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{assert_float_eq, rect};
+
+    /// Two objects; the best prediction finds the first, a spurious one follows, and the
+    /// third finds the second object.
+    fn scene() -> (Vec<Rect>, Vec<f64>, Vec<Rect>) {
+        let ground_truth = vec![rect(0, 0, 10, 10), rect(50, 50, 10, 10)];
+        let predictions = vec![rect(51, 50, 10, 10), rect(0, 1, 10, 10), rect(20, 20, 5, 5)];
+        let scores = vec![0.7, 0.9, 0.8];
+        (predictions, scores, ground_truth)
+    }
+
+    #[test]
+    fn test_match_detections() {
+        let (predictions, scores, ground_truth) = scene();
+        let matching = match_detections(&predictions, &scores, &ground_truth, 0.5);
+
+        let pairs: Vec<(usize, usize)> = matching
+            .true_positives
+            .iter()
+            .map(|m| (m.prediction, m.ground_truth))
+            .collect();
+        assert_eq!(pairs, vec![(1, 0), (0, 1)]);
+        assert_float_eq(matching.true_positives[0].iou, 90.0 / 110.0);
+        assert_eq!(matching.false_positives, vec![2]);
+        assert!(matching.false_negatives.is_empty());
+        assert_float_eq(matching.precision(), 2.0 / 3.0);
+        assert_float_eq(matching.recall(), 1.0);
+        assert_float_eq(matching.f1(), 0.8);
+
+        let strict = match_detections(&predictions, &scores, &ground_truth, 0.95);
+        assert!(strict.true_positives.is_empty());
+        assert_eq!(strict.false_negatives, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_each_ground_truth_is_matched_once() {
+        let ground_truth = vec![rect(0, 0, 10, 10)];
+        let predictions = vec![rect(0, 0, 10, 10), rect(0, 0, 10, 10)];
+        let matching = match_detections(&predictions, &[0.5, 0.5], &ground_truth, 0.5);
+        assert_eq!(matching.true_positives.len(), 1);
+        assert_eq!(matching.true_positives[0].prediction, 0);
+        assert_eq!(matching.false_positives, vec![1]);
+    }
+
+    #[test]
+    fn test_precision_recall_curve_and_average_precision() {
+        let (predictions, scores, ground_truth) = scene();
+
+        let curve = precision_recall_curve(&predictions, &scores, &ground_truth, 0.5);
+        let values: Vec<(f64, f64)> = curve.iter().map(|p| (p.precision, p.recall)).collect();
+        assert_eq!(values, vec![(1.0, 0.5), (0.5, 0.5), (2.0 / 3.0, 1.0)]);
+        assert_float_eq(curve[2].f1, 0.8);
+
+        let ap = average_precision(&predictions, &scores, &ground_truth, 0.5).unwrap();
+        assert_float_eq(ap, 0.5 + 0.5 * 2.0 / 3.0);
+
+        assert!(average_precision::<Rect>(&[], &[], &[], 0.5).is_none());
+        assert_eq!(average_precision(&[], &[], &ground_truth, 0.5), Some(0.0));
+    }
+
+    #[test]
+    fn test_mean_average_precision() {
+        let ground_truth = vec![
+            Annotation::from_rect(rect(0, 0, 10, 10), 0),
+            Annotation::from_rect(rect(50, 50, 10, 10), 1),
+        ];
+        // The second prediction has the right box but the wrong class.
+        let predictions = vec![
+            Annotation::from_rect(rect(0, 0, 10, 10), 0),
+            Annotation::from_rect(rect(50, 50, 10, 10), 0),
+        ];
+        let images = [ImageDetections {
+            predictions: &predictions,
+            scores: &[0.9, 0.8],
+            ground_truth: &ground_truth,
+        }];
+
+        assert_float_eq(mean_average_precision(&images, &[0.5]).unwrap(), 0.5);
+        assert!(mean_average_precision(&images, &[]).is_none());
+    }
+
+    #[test]
+    fn test_draw_matching() {
+        let (predictions, scores, ground_truth) = scene();
+        let matching = match_detections(&predictions, &scores, &ground_truth[..1], 0.5);
+        let mut canvas = RgbaImage::new(80, 80);
+        draw_matching_mut(&mut canvas, &predictions, &ground_truth[..1], &matching);
+
+        assert_eq!(*canvas.get_pixel(0, 1), Rgba([0, 255, 0, 255]));
+        assert_eq!(*canvas.get_pixel(20, 20), Rgba([255, 0, 0, 255]));
+        assert_eq!(*canvas.get_pixel(51, 50), Rgba([255, 0, 0, 255]));
+        assert_eq!(*canvas.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+    }
+}
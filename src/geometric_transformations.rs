@@ -0,0 +1,216 @@
+use crate::rect::RotatedRect;
+use image::Pixel;
+use imageproc::{
+    contours::Contour,
+    definitions::{Clamp, Image},
+    geometric_transformations::{Interpolation, Projection, warp_into},
+};
+use num::NumCast;
+
+/// Extracts the region enclosed by a rotated rectangle as an upright image.
+///
+/// The image is rotated about the rectangle's center by `-rect.angle`, so that the side
+/// along `rect.angle` becomes horizontal. Since [`RotatedRect`] keeps its angle in
+/// `[-45, 45)`, the result is upright to the nearest quarter turn; use
+/// `imageproc::geometric_transformations::rotate90` if the long side must be horizontal.
+///
+/// The rectangle is measured between pixel centers, as `min_area_rect` returns it for
+/// contour points, so a rectangle spanning pixels 0 to 10 yields an 11-pixel-wide crop.
+///
+/// # Arguments
+///
+/// * `image` - The source image.
+/// * `rect` - The region to extract, e.g. `RotatedRect::enclosing(&contour.points)`.
+/// * `interpolation` - How to sample between source pixels.
+/// * `padding` - Extra pixels to include on every side of the rectangle.
+/// * `default` - The value of output pixels that fall outside the source image.
+///
+/// # Returns
+///
+/// An image of `round(rect.width) + 1 + 2 * padding` by
+/// `round(rect.height) + 1 + 2 * padding` pixels, or `None` if a side is negative, not
+/// finite, or the size does not fit in a `u32`.
+pub fn crop_rotated_rect<P>(
+    image: &Image<P>,
+    rect: &RotatedRect,
+    interpolation: Interpolation,
+    padding: u32,
+    default: P,
+) -> Option<Image<P>>
+where
+    P: Pixel + Send + Sync,
+    <P as Pixel>::Subpixel: Send + Sync + Into<f32> + Clamp<f32>,
+{
+    let crop_size = |side: f64| {
+        let side = side.round();
+        if !(0.0..=u32::MAX as f64).contains(&side) {
+            return None;
+        }
+        (side as u32)
+            .checked_add(1)?
+            .checked_add(padding.checked_mul(2)?)
+    };
+    let width = crop_size(rect.width)?;
+    let height = crop_size(rect.height)?;
+
+    // Maps source coordinates to crop coordinates: move the rectangle's center to the
+    // origin, undo its rotation, then move it to the center of the crop.
+    let projection = Projection::translate(-rect.center.x as f32, -rect.center.y as f32)
+        .and_then(Projection::rotate(-rect.angle.to_radians() as f32))
+        .and_then(Projection::translate(
+            (width - 1) as f32 / 2.0,
+            (height - 1) as f32 / 2.0,
+        ));
+
+    let mut out = Image::new(width, height);
+    warp_into(image, &projection, interpolation, default, &mut out);
+    Some(out)
+}
+
+/// Extracts one upright crop per rotated rectangle, see [`crop_rotated_rect`].
+pub fn crop_rotated_rects<P>(
+    image: &Image<P>,
+    rects: &[RotatedRect],
+    interpolation: Interpolation,
+    padding: u32,
+    default: P,
+) -> Vec<Option<Image<P>>>
+where
+    P: Pixel + Send + Sync,
+    <P as Pixel>::Subpixel: Send + Sync + Into<f32> + Clamp<f32>,
+{
+    rects
+        .iter()
+        .map(|rect| crop_rotated_rect(image, rect, interpolation, padding, default))
+        .collect()
+}
+
+/// Extracts an upright crop of the minimum-area rectangle around each contour.
+///
+/// The rectangle of each contour is found with [`RotatedRect::enclosing`] and cropped with
+/// [`crop_rotated_rect`].
+///
+/// # Returns
+///
+/// One entry per contour, in the same order. Contours without points, or whose rectangle
+/// is too large to crop, yield `None`.
+pub fn crop_contours<P, T>(
+    image: &Image<P>,
+    contours: &[Contour<T>],
+    interpolation: Interpolation,
+    padding: u32,
+    default: P,
+) -> Vec<Option<Image<P>>>
+where
+    P: Pixel + Send + Sync,
+    <P as Pixel>::Subpixel: Send + Sync + Into<f32> + Clamp<f32>,
+    T: NumCast + Copy + PartialOrd,
+{
+    contours
+        .iter()
+        .map(|contour| {
+            if contour.points.is_empty() {
+                return None;
+            }
+            let rect = RotatedRect::enclosing(&contour.points);
+            crop_rotated_rect(image, &rect, interpolation, padding, default)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+    use imageproc::{contours::BorderType, point::Point};
+
+    /// A 40x40 image with a bright 21x5 bar centered at (20, 20), rotated by `angle`
+    /// degrees.
+    fn rotated_bar(angle: f64) -> GrayImage {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        GrayImage::from_fn(40, 40, |x, y| {
+            let (dx, dy) = (x as f64 - 20.0, y as f64 - 20.0);
+            let along = dx * cos + dy * sin;
+            let across = -dx * sin + dy * cos;
+            if along.abs() <= 10.5 && across.abs() <= 2.5 {
+                Luma([255])
+            } else {
+                Luma([0])
+            }
+        })
+    }
+
+    #[test]
+    fn test_crop_axis_aligned_rect() {
+        let image = GrayImage::from_fn(10, 10, |x, y| Luma([(10 * y + x) as u8]));
+        let rect = RotatedRect::new(Point::new(4.0, 5.0), 2.0, 4.0, 0.0);
+
+        let crop = crop_rotated_rect(&image, &rect, Interpolation::Nearest, 0, Luma([0])).unwrap();
+
+        assert_eq!(crop.dimensions(), (3, 5));
+        assert_eq!(crop.get_pixel(0, 0)[0], 33);
+        assert_eq!(crop.get_pixel(2, 4)[0], 75);
+
+        let padded =
+            crop_rotated_rect(&image, &rect, Interpolation::Nearest, 1, Luma([0])).unwrap();
+        assert_eq!(padded.dimensions(), (5, 7));
+        assert_eq!(padded.get_pixel(0, 0)[0], 22);
+    }
+
+    #[test]
+    fn test_crop_rejects_invalid_sizes() {
+        let image = GrayImage::new(10, 10);
+        let crop = |width: f64, padding: u32| {
+            let mut rect = RotatedRect::new(Point::new(4.0, 5.0), 2.0, 4.0, 0.0);
+            rect.width = width;
+            crop_rotated_rect(&image, &rect, Interpolation::Nearest, padding, Luma([0]))
+        };
+
+        assert!(crop(f64::NAN, 0).is_none());
+        assert!(crop(f64::INFINITY, 0).is_none());
+        assert!(crop(-3.0, 0).is_none());
+        assert!(crop(u32::MAX as f64, 0).is_none());
+        assert!(crop(2.0, u32::MAX / 2).is_none());
+        assert!(crop(2.0, u32::MAX).is_none());
+    }
+
+    #[test]
+    fn test_crop_deskews_rotated_bar() {
+        let image = rotated_bar(30.0);
+        let rect = RotatedRect::new(Point::new(20.0, 20.0), 20.0, 4.0, 30.0);
+
+        let crop = crop_rotated_rect(&image, &rect, Interpolation::Bilinear, 0, Luma([0])).unwrap();
+
+        assert_eq!(crop.dimensions(), (21, 5));
+        // The deskewed bar fills the crop, but for a little bilinear blur on the border.
+        let bright = crop.pixels().filter(|p| p[0] > 127).count();
+        assert!(bright >= 21 * 5 * 9 / 10, "only {bright} bright pixels");
+    }
+
+    #[test]
+    fn test_crop_contours() {
+        let image = rotated_bar(0.0);
+        let contours = vec![
+            Contour::new(
+                vec![
+                    Point::new(10, 18),
+                    Point::new(30, 18),
+                    Point::new(30, 22),
+                    Point::new(10, 22),
+                ],
+                BorderType::Outer,
+                None,
+            ),
+            Contour::new(Vec::new(), BorderType::Outer, None),
+        ];
+
+        let crops = crop_contours(&image, &contours, Interpolation::Nearest, 2, Luma([0]));
+
+        assert_eq!(crops.len(), 2);
+        let crop = crops[0].as_ref().unwrap();
+        assert_eq!(crop.dimensions(), (25, 9));
+        assert_eq!(crop.get_pixel(12, 4)[0], 255);
+        assert_eq!(crop.get_pixel(0, 0)[0], 0);
+        assert!(crops[1].is_none());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::contours::signed_area;
+use imageproc::point::Point;
+
+/// The z-component of the cross product `(b - a) x (c - a)`.
+///
+/// Positive when `a -> b -> c` turns clockwise as displayed (y axis pointing down),
+/// negative when it turns counter-clockwise, and zero when the points are collinear.
+pub(crate) fn cross(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Whether `p`, known to be collinear with `a` and `b`, lies on the segment `a`-`b`.
+fn within_segment(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> bool {
+    p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0) && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1)
+}
+
+/// Returns a point shared by the segments `a`-`b` and `c`-`d`, if any.
+///
+/// Proper crossings return the crossing point. Touching or collinear overlapping segments
+/// return one of the shared endpoints.
+pub(crate) fn segment_intersection(
+    a: (f64, f64),
+    b: (f64, f64),
+    c: (f64, f64),
+    d: (f64, f64),
+) -> Option<(f64, f64)> {
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        let t = d1 / (d1 - d2);
+        return Some((a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)));
+    }
+
+    if d1 == 0.0 && within_segment(c, d, a) {
+        Some(a)
+    } else if d2 == 0.0 && within_segment(c, d, b) {
+        Some(b)
+    } else if d3 == 0.0 && within_segment(a, b, c) {
+        Some(c)
+    } else if d4 == 0.0 && within_segment(a, b, d) {
+        Some(d)
+    } else {
+        None
+    }
+}
+
+/// The area of the intersection of two convex polygons, given as rings of points in either
+/// winding direction.
+///
+/// Uses Sutherland-Hodgman clipping, which is exact for convex clip polygons.
+pub(crate) fn convex_intersection_area(subject: &[(f64, f64)], clip: &[(f64, f64)]) -> f64 {
+    let ring_area = |ring: &[(f64, f64)]| {
+        let points: Vec<Point<f64>> = ring.iter().map(|&(x, y)| Point::new(x, y)).collect();
+        signed_area(&points)
+    };
+    let orientation = ring_area(clip).signum();
+    if orientation == 0.0 || ring_area(subject) == 0.0 {
+        return 0.0;
+    }
+
+    let mut output = subject.to_vec();
+    for (i, &a) in clip.iter().enumerate() {
+        let b = clip[(i + 1) % clip.len()];
+        let input = std::mem::take(&mut output);
+        if input.is_empty() {
+            break;
+        }
+        let side = |p| cross(a, b, p) * orientation;
+        let crossing = |p: (f64, f64), q: (f64, f64)| {
+            let t = side(p) / (side(p) - side(q));
+            (p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1))
+        };
+
+        for (j, &current) in input.iter().enumerate() {
+            let previous = input[(j + input.len() - 1) % input.len()];
+            match (side(previous) >= 0.0, side(current) >= 0.0) {
+                (true, true) => output.push(current),
+                (true, false) => output.push(crossing(previous, current)),
+                (false, true) => {
+                    output.push(crossing(previous, current));
+                    output.push(current);
+                }
+                (false, false) => {}
+            }
+        }
+    }
+
+    ring_area(&output).abs()
+}
+
+/// The distance from `p` to the segment `a`-`b`.
+pub(crate) fn point_segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    (p.0 - (a.0 + t * dx)).hypot(p.1 - (a.1 + t * dy))
+}
+
+/// The distance from `p` to the closed ring `points`, 0 if `p` lies on or inside it.
+///
+/// Returns infinity for an empty ring.
+pub(crate) fn point_polygon_distance(p: (f64, f64), points: &[(f64, f64)]) -> f64 {
+    if points.is_empty() {
+        return f64::INFINITY;
+    }
+
+    let mut inside = false;
+    let mut distance = f64::INFINITY;
+    let mut previous = points[points.len() - 1];
+    for &current in points {
+        distance = distance.min(point_segment_distance(p, previous, current));
+        // Even-odd ray casting towards +x.
+        if (current.1 > p.1) != (previous.1 > p.1)
+            && p.0
+                < previous.0
+                    + (p.1 - previous.1) / (current.1 - previous.1) * (current.0 - previous.0)
+        {
+            inside = !inside;
+        }
+        previous = current;
+    }
+
+    if inside { 0.0 } else { distance }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_intersection() {
+        // Proper crossing.
+        assert_eq!(
+            segment_intersection((0.0, 0.0), (2.0, 2.0), (0.0, 2.0), (2.0, 0.0)),
+            Some((1.0, 1.0))
+        );
+        // Touching at an endpoint.
+        assert_eq!(
+            segment_intersection((0.0, 0.0), (1.0, 0.0), (1.0, 0.0), (1.0, 5.0)),
+            Some((1.0, 0.0))
+        );
+        // Collinear but disjoint.
+        assert_eq!(
+            segment_intersection((0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0)),
+            None
+        );
+        // Parallel.
+        assert_eq!(
+            segment_intersection((0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_convex_intersection_area() {
+        let square = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        let shifted = [(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)];
+        assert_eq!(convex_intersection_area(&square, &shifted), 1.0);
+
+        // Winding of either polygon does not matter.
+        let mut reversed = shifted;
+        reversed.reverse();
+        assert_eq!(convex_intersection_area(&square, &reversed), 1.0);
+        assert_eq!(convex_intersection_area(&reversed, &square), 1.0);
+
+        // A diamond inscribed in the square covers half of it.
+        let diamond = [(1.0, 0.0), (2.0, 1.0), (1.0, 2.0), (0.0, 1.0)];
+        assert_eq!(convex_intersection_area(&square, &diamond), 2.0);
+
+        let far = [(5.0, 5.0), (6.0, 5.0), (6.0, 6.0), (5.0, 6.0)];
+        assert_eq!(convex_intersection_area(&square, &far), 0.0);
+    }
+
+    #[test]
+    fn test_point_polygon_distance() {
+        let square = [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)];
+        assert_eq!(point_polygon_distance((2.0, 2.0), &square), 0.0);
+        assert_eq!(point_polygon_distance((4.0, 1.0), &square), 0.0);
+        assert_eq!(point_polygon_distance((7.0, 2.0), &square), 3.0);
+        assert_eq!(point_polygon_distance((7.0, 8.0), &square), 5.0);
+        assert_eq!(point_polygon_distance((1.0, 1.0), &[(1.0, 1.0)]), 0.0);
+        assert_eq!(point_polygon_distance((1.0, 1.0), &[]), f64::INFINITY);
+    }
+}
@@ -2,7 +2,17 @@
 //!
 //! The utility functions are organized into modules the same categories (as possible) as in [imageproc].
 
+pub mod annotations;
 mod colors;
 pub mod contours;
+mod drawing;
+pub mod evaluation;
+pub mod geometric_transformations;
+mod geometry;
 pub mod rect;
 pub mod region_labelling;
+pub mod spatial_index;
+#[cfg(test)]
+mod test_util;
+mod union_find;
+mod watershed;
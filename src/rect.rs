@@ -1,6 +1,17 @@
-use image::math::Rect;
-use imageproc::point::Point;
-use num_traits::{Num, ToPrimitive};
+use crate::{
+    colors::generate_contrasting_colors, drawing::draw_number_mut,
+    geometry::convex_intersection_area, union_find::UnionFind,
+};
+use image::{Rgba, RgbaImage, math::Rect};
+use imageproc::{
+    contours::Contour,
+    drawing::{draw_hollow_rect_mut, draw_line_segment_mut},
+    geometry::{contour_area, convex_hull, min_area_rect},
+    point::Point,
+};
+use num_traits::{Num, NumCast, ToPrimitive};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 /// Calculates the axis-aligned bounding box of a rotated rectangle's vertices.
 ///
@@ -12,6 +23,10 @@ use num_traits::{Num, ToPrimitive};
 /// This version is generic over numeric types that implement `PartialOrd`, making it
 /// suitable for both integer and floating-point coordinates.
 ///
+/// Coordinates that cannot be represented as `u32` (negative values, NaN) become 0.
+/// Use [`to_signed_bounding_box`] to keep the real extents, or [`to_clipped_bounding_box`]
+/// to clip the box to an image.
+///
 /// # Arguments
 ///
 /// * `vertices` - An array of 4 `Point<T>` representing the corners of a rectangle.
@@ -89,9 +104,1162 @@ where
     }
 }
 
+/// Calculates the axis-aligned bounding box of any set of points, keeping negative
+/// coordinates.
+///
+/// Unlike [`to_axis_aligned_bounding_box`], which maps anything it cannot represent as a
+/// `u32` to 0, this returns the real extents as an `imageproc::rect::Rect`. A box spanning
+/// x = -10..50 therefore starts at -10 and is 60 wide.
+///
+/// The box is snapped outwards to whole pixels (`floor` of the minimum, `ceil` of the
+/// maximum), so it always encloses every point. Since `imageproc::rect::Rect` cannot be
+/// empty, degenerate extents (e.g. a single point) get a width or height of 1.
+///
+/// # Arguments
+///
+/// * `points` - Any slice of points, e.g. the output of `min_area_rect` or a contour.
+///
+/// # Returns
+///
+/// `None` if `points` is empty, or if a coordinate is NaN, infinite or outside the `i32`
+/// range.
+///
+/// # Examples
+///
+/// ```
+/// use imageproc::point::Point;
+/// use image_debug_utils::rect::to_signed_bounding_box;
+///
+/// let points = [Point::new(-10.0, -20.0), Point::new(50.0, 30.0)];
+/// let bounding_box = to_signed_bounding_box(&points).unwrap();
+///
+/// assert_eq!((bounding_box.left(), bounding_box.top()), (-10, -20));
+/// assert_eq!((bounding_box.width(), bounding_box.height()), (60, 50));
+/// ```
+pub fn to_signed_bounding_box<T>(points: &[Point<T>]) -> Option<imageproc::rect::Rect>
+where
+    T: Copy + ToPrimitive,
+{
+    let (min_x, min_y, max_x, max_y) = point_extent(points)?;
+
+    let to_i32 = |v: f64| {
+        if v >= i32::MIN as f64 && v <= i32::MAX as f64 {
+            Some(v as i32)
+        } else {
+            None
+        }
+    };
+    let left = to_i32(min_x.floor())?;
+    let top = to_i32(min_y.floor())?;
+    let right = to_i32(max_x.ceil())?;
+    let bottom = to_i32(max_y.ceil())?;
+
+    let width = (right as i64 - left as i64).max(1) as u32;
+    let height = (bottom as i64 - top as i64).max(1) as u32;
+
+    Some(imageproc::rect::Rect::at(left, top).of_size(width, height))
+}
+
+/// Calculates the axis-aligned bounding box of any set of points, clipped to an image.
+///
+/// The box is computed as in [`to_signed_bounding_box`] and then intersected with
+/// `0..image_width` and `0..image_height`.
+///
+/// # Returns
+///
+/// `None` if `points` is empty or invalid (see [`to_signed_bounding_box`]), or if the box
+/// lies entirely outside the image.
+///
+/// # Examples
+///
+/// ```
+/// use imageproc::point::Point;
+/// use image_debug_utils::rect::to_clipped_bounding_box;
+///
+/// let points = [Point::new(-10, 5), Point::new(50, 30)];
+/// let clipped = to_clipped_bounding_box(&points, 40, 40).unwrap();
+/// assert_eq!((clipped.x, clipped.y, clipped.width, clipped.height), (0, 5, 40, 25));
+///
+/// let outside = [Point::new(-10, -10), Point::new(-5, -5)];
+/// assert_eq!(to_clipped_bounding_box(&outside, 40, 40), None);
+/// ```
+pub fn to_clipped_bounding_box<T>(
+    points: &[Point<T>],
+    image_width: u32,
+    image_height: u32,
+) -> Option<Rect>
+where
+    T: Copy + ToPrimitive,
+{
+    clip_to_image(to_signed_bounding_box(points)?, image_width, image_height)
+}
+
+/// Intersects a signed box with `0..image_width` and `0..image_height`, or returns `None` if
+/// nothing is left.
+fn clip_to_image(
+    signed: imageproc::rect::Rect,
+    image_width: u32,
+    image_height: u32,
+) -> Option<Rect> {
+    let clip = |start: i32, length: u32, limit: u32| -> Option<(u32, u32)> {
+        let start = start as i64;
+        let end = start + length as i64;
+        let clipped_start = start.max(0);
+        let clipped_end = end.min(limit as i64);
+        (clipped_end > clipped_start)
+            .then(|| (clipped_start as u32, (clipped_end - clipped_start) as u32))
+    };
+
+    let (x, width) = clip(signed.left(), signed.width(), image_width)?;
+    let (y, height) = clip(signed.top(), signed.height(), image_height)?;
+
+    Some(Rect {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// Calculates the upright bounding box of a contour or any other set of points.
+///
+/// This replaces `to_axis_aligned_bounding_box(&min_area_rect(points))` when only the
+/// upright box is needed: it is a single min/max pass over the points instead of a convex
+/// hull and rotating calipers. For shapes that are not upright the result is also tighter,
+/// since the upright box of a rotated rectangle encloses more than the shape. It has the
+/// signed semantics of [`to_signed_bounding_box`];
+/// use [`to_clipped_bounding_box`] for the clipped ones.
+///
+/// # Returns
+///
+/// `None` if `points` is empty or invalid (see [`to_signed_bounding_box`]).
+///
+/// # Examples
+///
+/// ```
+/// use imageproc::point::Point;
+/// use image_debug_utils::rect::bounding_rect;
+///
+/// let points = [Point::new(3, 4), Point::new(10, 2), Point::new(-1, 8)];
+/// let bounding_box = bounding_rect(&points).unwrap();
+///
+/// assert_eq!((bounding_box.left(), bounding_box.top()), (-1, 2));
+/// assert_eq!((bounding_box.width(), bounding_box.height()), (11, 6));
+/// ```
+pub fn bounding_rect<T>(points: &[Point<T>]) -> Option<imageproc::rect::Rect>
+where
+    T: Copy + ToPrimitive,
+{
+    to_signed_bounding_box(points)
+}
+
+/// Calculates [`bounding_rect`] for every contour, in input order.
+pub fn bounding_rects<T>(contours: &[Contour<T>]) -> Vec<Option<imageproc::rect::Rect>>
+where
+    T: Copy + ToPrimitive,
+{
+    contours.iter().map(|c| bounding_rect(&c.points)).collect()
+}
+
+/// Calculates [`bounding_rect`] for every contour and clips it to the image as
+/// [`to_clipped_bounding_box`] does, in input order.
+pub fn clipped_bounding_rects<T>(
+    contours: &[Contour<T>],
+    image_width: u32,
+    image_height: u32,
+) -> Vec<Option<Rect>>
+where
+    T: Copy + ToPrimitive,
+{
+    contours
+        .iter()
+        .map(|c| bounding_rect(&c.points).and_then(|r| clip_to_image(r, image_width, image_height)))
+        .collect()
+}
+
+/// Parallel version of [`bounding_rects`], worthwhile for tens of thousands of contours.
+#[cfg(feature = "rayon")]
+pub fn par_bounding_rects<T>(contours: &[Contour<T>]) -> Vec<Option<imageproc::rect::Rect>>
+where
+    T: Copy + ToPrimitive + Sync,
+{
+    contours
+        .par_iter()
+        .map(|c| bounding_rect(&c.points))
+        .collect()
+}
+
+/// Parallel version of [`clipped_bounding_rects`].
+#[cfg(feature = "rayon")]
+pub fn par_clipped_bounding_rects<T>(
+    contours: &[Contour<T>],
+    image_width: u32,
+    image_height: u32,
+) -> Vec<Option<Rect>>
+where
+    T: Copy + ToPrimitive + Sync,
+{
+    contours
+        .par_iter()
+        .map(|c| bounding_rect(&c.points).and_then(|r| clip_to_image(r, image_width, image_height)))
+        .collect()
+}
+
+/// Returns `(min_x, min_y, max_x, max_y)` of `points` as `f64`, or `None` if there are no
+/// points or any coordinate is not finite.
+fn point_extent<T>(points: &[Point<T>]) -> Option<(f64, f64, f64, f64)>
+where
+    T: Copy + ToPrimitive,
+{
+    let mut extent: Option<(f64, f64, f64, f64)> = None;
+    for p in points {
+        let x = p.x.to_f64().filter(|v| v.is_finite())?;
+        let y = p.y.to_f64().filter(|v| v.is_finite())?;
+        extent = Some(match extent {
+            None => (x, y, x, y),
+            Some((min_x, min_y, max_x, max_y)) => {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            }
+        });
+    }
+    extent
+}
+
+/// Grows a box by `pixels` on every side.
+///
+/// Since `image::math::Rect` cannot hold negative coordinates, growth past the left or top
+/// image edge is cut off at 0. Use [`clamp_to_image`] to also cut it at the right and
+/// bottom edges.
+pub fn pad(rect: Rect, pixels: u32) -> Rect {
+    let p = pixels as f64;
+    let (left, top, right, bottom) = edges(&rect);
+    rect_from_edges(left - p, top - p, right + p, bottom + p)
+}
+
+/// Grows a box on every side by `fraction` of its width (left and right) and height (top
+/// and bottom). `0.1` adds 10% on each side, i.e. 20% in total per dimension.
+///
+/// Fractional results are rounded outwards, so the box never shrinks for a non-negative
+/// `fraction`. Growth past the left or top edge is cut off at 0, see [`pad`].
+pub fn pad_relative(rect: Rect, fraction: f64) -> Rect {
+    let dx = rect.width as f64 * fraction;
+    let dy = rect.height as f64 * fraction;
+    let (left, top, right, bottom) = edges(&rect);
+    rect_from_edges(left - dx, top - dy, right + dx, bottom + dy)
+}
+
+/// Scales a box by `factor` around its center. `2.0` doubles the width and height.
+///
+/// Fractional results are rounded outwards. Growth past the left or top edge is cut off
+/// at 0, see [`pad`].
+///
+/// # Panics
+///
+/// Panics if `factor` is negative or not finite.
+pub fn scale_about_center(rect: Rect, factor: f64) -> Rect {
+    assert!(
+        factor.is_finite() && factor >= 0.0,
+        "factor must be a non-negative finite number"
+    );
+    let (cx, cy) = (
+        rect.x as f64 + rect.width as f64 / 2.0,
+        rect.y as f64 + rect.height as f64 / 2.0,
+    );
+    let (half_width, half_height) = (
+        rect.width as f64 * factor / 2.0,
+        rect.height as f64 * factor / 2.0,
+    );
+    rect_from_edges(
+        cx - half_width,
+        cy - half_height,
+        cx + half_width,
+        cy + half_height,
+    )
+}
+
+/// Grows the shorter dimension of a box, symmetrically around its center, until
+/// `width / height` equals `aspect_ratio`.
+///
+/// This is what classifiers with a fixed input shape need: the object stays centered and
+/// nothing of it is cut off. Fractional results are rounded outwards. Growth past the left
+/// or top edge is cut off at 0, see [`pad`].
+///
+/// # Panics
+///
+/// Panics if `aspect_ratio` is not a positive finite number.
+pub fn expand_to_aspect_ratio(rect: Rect, aspect_ratio: f64) -> Rect {
+    assert!(
+        aspect_ratio.is_finite() && aspect_ratio > 0.0,
+        "aspect_ratio must be a positive finite number"
+    );
+    let (width, height) = (rect.width as f64, rect.height as f64);
+    let (new_width, new_height) = if width < height * aspect_ratio {
+        (height * aspect_ratio, height)
+    } else {
+        (width, width / aspect_ratio)
+    };
+    let dx = (new_width - width) / 2.0;
+    let dy = (new_height - height) / 2.0;
+    let (left, top, right, bottom) = edges(&rect);
+    rect_from_edges(left - dx, top - dy, right + dx, bottom + dy)
+}
+
+/// Clips a box to an image of the given dimensions.
+///
+/// # Returns
+///
+/// `None` if nothing of the box lies inside the image.
+pub fn clamp_to_image(rect: Rect, image_width: u32, image_height: u32) -> Option<Rect> {
+    let right = (rect.x as u64 + rect.width as u64).min(image_width as u64);
+    let bottom = (rect.y as u64 + rect.height as u64).min(image_height as u64);
+    (right > rect.x as u64 && bottom > rect.y as u64).then(|| Rect {
+        x: rect.x,
+        y: rect.y,
+        width: (right - rect.x as u64) as u32,
+        height: (bottom - rect.y as u64) as u32,
+    })
+}
+
+/// A mapping of boxes from one image size to another, such as the resize or letterbox
+/// applied before running a classifier or detector.
+///
+/// A point `p` in the source image maps to `p * scale + offset` in the destination.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResizeTransform {
+    /// Horizontal and vertical scale factors.
+    pub scale: (f64, f64),
+    /// Horizontal and vertical offsets, in destination pixels.
+    pub offset: (f64, f64),
+}
+
+impl ResizeTransform {
+    /// A plain resize from `source` to `destination` dimensions, stretching as needed.
+    pub fn resize(source: (u32, u32), destination: (u32, u32)) -> Self {
+        ResizeTransform {
+            scale: (
+                destination.0 as f64 / source.0 as f64,
+                destination.1 as f64 / source.1 as f64,
+            ),
+            offset: (0.0, 0.0),
+        }
+    }
+
+    /// A letterbox from `source` to `destination` dimensions: the image is scaled by a
+    /// single factor so that it fits, and centered with padding on the remaining sides.
+    pub fn letterbox(source: (u32, u32), destination: (u32, u32)) -> Self {
+        let scale =
+            (destination.0 as f64 / source.0 as f64).min(destination.1 as f64 / source.1 as f64);
+        ResizeTransform {
+            scale: (scale, scale),
+            offset: (
+                (destination.0 as f64 - source.0 as f64 * scale) / 2.0,
+                (destination.1 as f64 - source.1 as f64 * scale) / 2.0,
+            ),
+        }
+    }
+
+    /// Maps a box from the source to the destination image.
+    ///
+    /// Fractional results are rounded outwards.
+    pub fn apply(&self, rect: Rect) -> Rect {
+        let (left, top, right, bottom) = edges(&rect);
+        rect_from_edges(
+            left * self.scale.0 + self.offset.0,
+            top * self.scale.1 + self.offset.1,
+            right * self.scale.0 + self.offset.0,
+            bottom * self.scale.1 + self.offset.1,
+        )
+    }
+
+    /// Maps a box from the destination back to the source image, e.g. a detection made on
+    /// the letterboxed input.
+    ///
+    /// Fractional results are rounded outwards. Parts of the box that fall into the left or
+    /// top letterbox padding are cut off at 0; use [`clamp_to_image`] with the source
+    /// dimensions to also cut the right and bottom padding.
+    pub fn invert(&self, rect: Rect) -> Rect {
+        let (left, top, right, bottom) = edges(&rect);
+        rect_from_edges(
+            (left - self.offset.0) / self.scale.0,
+            (top - self.offset.1) / self.scale.1,
+            (right - self.offset.0) / self.scale.0,
+            (bottom - self.offset.1) / self.scale.1,
+        )
+    }
+}
+
+/// Returns `(left, top, right, bottom)` of a box as `f64`, with exclusive right and bottom.
+fn edges(rect: &Rect) -> (f64, f64, f64, f64) {
+    (
+        rect.x as f64,
+        rect.y as f64,
+        rect.x as f64 + rect.width as f64,
+        rect.y as f64 + rect.height as f64,
+    )
+}
+
+/// Snaps a coordinate within floating-point noise of a whole pixel to that pixel, so that
+/// subsequent rounding or truncation does not flip it to the neighbouring pixel.
+fn snap_to_pixel(v: f64) -> f64 {
+    if (v - v.round()).abs() < 1e-9 {
+        v.round()
+    } else {
+        v
+    }
+}
+
+/// Builds a box from `f64` edges, rounding outwards and cutting off negative coordinates.
+pub(crate) fn rect_from_edges(left: f64, top: f64, right: f64, bottom: f64) -> Rect {
+    let to_u32 = |v: f64| v.clamp(0.0, u32::MAX as f64) as u32;
+    let x = to_u32(snap_to_pixel(left).floor());
+    let y = to_u32(snap_to_pixel(top).floor());
+    let right = to_u32(snap_to_pixel(right).ceil()).max(x);
+    let bottom = to_u32(snap_to_pixel(bottom).ceil()).max(y);
+    Rect {
+        x,
+        y,
+        width: right - x,
+        height: bottom - y,
+    }
+}
+
+/// A rectangle of arbitrary orientation, described by its center, size and angle.
+///
+/// This is a named form of the four corners returned by `imageproc::geometry::min_area_rect`.
+/// It uses the following canonical conventions, so that every rectangle has exactly one
+/// representation:
+///
+/// * `angle` is in degrees, in `[-45, 45)`, measured from the positive x axis towards the
+///   positive y axis (i.e. clockwise as displayed, with the y axis pointing down).
+/// * `width` is the length of the side running along `angle`, `height` the length of the
+///   perpendicular side. Either may be the longer one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotatedRect {
+    /// The center of the rectangle.
+    pub center: Point<f64>,
+    /// The length of the side along `angle`.
+    pub width: f64,
+    /// The length of the side perpendicular to `angle`.
+    pub height: f64,
+    /// The rotation in degrees, in `[-45, 45)`.
+    pub angle: f64,
+}
+
+impl RotatedRect {
+    /// Creates a rotated rectangle, normalizing `angle` into `[-45, 45)`.
+    ///
+    /// Rotating by a multiple of 90 degrees swaps `width` and `height` as needed, so the
+    /// result describes the same rectangle.
+    pub fn new(center: Point<f64>, width: f64, height: f64, angle: f64) -> Self {
+        let mut angle = angle.rem_euclid(180.0);
+        let (mut width, mut height) = (width.abs(), height.abs());
+        while angle >= 45.0 {
+            angle -= 90.0;
+            std::mem::swap(&mut width, &mut height);
+        }
+        RotatedRect {
+            center,
+            width,
+            height,
+            angle,
+        }
+    }
+
+    /// Creates a rotated rectangle from its four corners, given in order around the
+    /// rectangle, such as the output of `min_area_rect`.
+    ///
+    /// The center is the mean of the corners, and the two sides are taken from the edges
+    /// `corners[0] -> corners[1]` and `corners[1] -> corners[2]`.
+    pub fn from_corners<T>(corners: &[Point<T>; 4]) -> Self
+    where
+        T: Copy + ToPrimitive,
+    {
+        let c = corners.map(|p| {
+            (
+                p.x.to_f64().unwrap_or(f64::NAN),
+                p.y.to_f64().unwrap_or(f64::NAN),
+            )
+        });
+        let center = Point::new(
+            c.iter().map(|p| p.0).sum::<f64>() / 4.0,
+            c.iter().map(|p| p.1).sum::<f64>() / 4.0,
+        );
+        let first_side = (c[1].0 - c[0].0, c[1].1 - c[0].1);
+        let second_side = (c[2].0 - c[1].0, c[2].1 - c[1].1);
+
+        // Degenerate rectangles have a zero-length first side; fall back to the second.
+        let angle = if first_side.0 == 0.0 && first_side.1 == 0.0 {
+            second_side.1.atan2(second_side.0).to_degrees() - 90.0
+        } else {
+            first_side.1.atan2(first_side.0).to_degrees()
+        };
+
+        RotatedRect::new(
+            center,
+            first_side.0.hypot(first_side.1),
+            second_side.0.hypot(second_side.1),
+            angle,
+        )
+    }
+
+    /// Finds the minimum-area rectangle enclosing `points` with `min_area_rect`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty, as `min_area_rect` does.
+    pub fn enclosing<T>(points: &[Point<T>]) -> Self
+    where
+        T: NumCast + Copy + PartialOrd,
+    {
+        RotatedRect::from_corners(&min_area_rect(points))
+    }
+
+    /// Returns the four corners in canonical order.
+    ///
+    /// For an unrotated rectangle this is [top left, top right, bottom right, bottom left],
+    /// matching `min_area_rect`. In general the corners run clockwise as displayed,
+    /// starting from the corner at `center - width / 2 - height / 2` in the rectangle's own
+    /// frame.
+    pub fn corners(&self) -> [Point<f64>; 4] {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let u = (cos * self.width / 2.0, sin * self.width / 2.0);
+        let v = (-sin * self.height / 2.0, cos * self.height / 2.0);
+        let c = self.center;
+        [
+            Point::new(c.x - u.0 - v.0, c.y - u.1 - v.1),
+            Point::new(c.x + u.0 - v.0, c.y + u.1 - v.1),
+            Point::new(c.x + u.0 + v.0, c.y + u.1 + v.1),
+            Point::new(c.x - u.0 + v.0, c.y - u.1 + v.1),
+        ]
+    }
+
+    /// The area of the rectangle.
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    /// The ratio of the long side to the short side, always `>= 1`.
+    ///
+    /// This is the same measure `remove_hypotenuse_in_place` filters on. Returns
+    /// `f64::INFINITY` if the short side has zero length.
+    pub fn aspect_ratio(&self) -> f64 {
+        let long = self.width.max(self.height);
+        let short = self.width.min(self.height);
+        if short == 0.0 {
+            f64::INFINITY
+        } else {
+            long / short
+        }
+    }
+
+    /// Whether `point` lies inside the rectangle or on its border.
+    pub fn contains<T>(&self, point: Point<T>) -> bool
+    where
+        T: Copy + ToPrimitive,
+    {
+        let (Some(x), Some(y)) = (point.x.to_f64(), point.y.to_f64()) else {
+            return false;
+        };
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let (dx, dy) = (x - self.center.x, y - self.center.y);
+        let along = dx * cos + dy * sin;
+        let across = -dx * sin + dy * cos;
+        // A small tolerance keeps points on the border inside despite rounding.
+        let eps = 1e-9 * (1.0 + self.width.max(self.height));
+        along.abs() <= self.width / 2.0 + eps && across.abs() <= self.height / 2.0 + eps
+    }
+
+    /// The axis-aligned bounding box of the rectangle, see [`to_axis_aligned_bounding_box`].
+    ///
+    /// Corners within floating-point noise of a whole pixel are snapped to it first, so a
+    /// corner computed as `9.999999999` does not truncate to `9`.
+    pub fn to_axis_aligned_bounding_box(&self) -> Rect {
+        let corners = self
+            .corners()
+            .map(|p| Point::new(snap_to_pixel(p.x), snap_to_pixel(p.y)));
+        to_axis_aligned_bounding_box(&corners)
+    }
+}
+
+impl<T> From<[Point<T>; 4]> for RotatedRect
+where
+    T: Copy + ToPrimitive,
+{
+    fn from(corners: [Point<T>; 4]) -> Self {
+        RotatedRect::from_corners(&corners)
+    }
+}
+
+impl From<RotatedRect> for [Point<f64>; 4] {
+    fn from(rect: RotatedRect) -> Self {
+        rect.corners()
+    }
+}
+
+/// Overlap measures between two boxes of the same kind.
+///
+/// Implemented for axis-aligned `image::math::Rect`s and for [`RotatedRect`]s, so that
+/// [`non_max_suppression`] and [`soft_non_max_suppression`] work with either.
+pub trait Overlap {
+    /// The area of the box.
+    fn area(&self) -> f64;
+
+    /// The area of the intersection of the two boxes.
+    fn intersection_area(&self, other: &Self) -> f64;
+
+    /// The area of the smallest enclosing region used by [`Overlap::giou`]: the enclosing
+    /// axis-aligned box for `Rect`s, the convex hull of both boxes for `RotatedRect`s.
+    fn enclosing_area(&self, other: &Self) -> f64;
+
+    /// Intersection over union, in `[0, 1]`. Two empty boxes have an IoU of 0.
+    fn iou(&self, other: &Self) -> f64 {
+        let intersection = self.intersection_area(other);
+        let union = self.area() + other.area() - intersection;
+        if union <= 0.0 {
+            0.0
+        } else {
+            intersection / union
+        }
+    }
+
+    /// Generalized intersection over union, in `[-1, 1]`.
+    ///
+    /// Unlike IoU, GIoU keeps decreasing as disjoint boxes move further apart, which makes
+    /// it more informative when comparing poorly matching boxes.
+    fn giou(&self, other: &Self) -> f64 {
+        let intersection = self.intersection_area(other);
+        let union = self.area() + other.area() - intersection;
+        let enclosing = self.enclosing_area(other);
+        if union <= 0.0 || enclosing <= 0.0 {
+            return 0.0;
+        }
+        intersection / union - (enclosing - union) / enclosing
+    }
+}
+
+impl Overlap for Rect {
+    fn area(&self) -> f64 {
+        self.width as f64 * self.height as f64
+    }
+
+    fn intersection_area(&self, other: &Self) -> f64 {
+        let left = self.x.max(other.x) as f64;
+        let top = self.y.max(other.y) as f64;
+        let right = (self.x as f64 + self.width as f64).min(other.x as f64 + other.width as f64);
+        let bottom = (self.y as f64 + self.height as f64).min(other.y as f64 + other.height as f64);
+        (right - left).max(0.0) * (bottom - top).max(0.0)
+    }
+
+    fn enclosing_area(&self, other: &Self) -> f64 {
+        let left = self.x.min(other.x) as f64;
+        let top = self.y.min(other.y) as f64;
+        let right = (self.x as f64 + self.width as f64).max(other.x as f64 + other.width as f64);
+        let bottom = (self.y as f64 + self.height as f64).max(other.y as f64 + other.height as f64);
+        (right - left) * (bottom - top)
+    }
+}
+
+impl Overlap for RotatedRect {
+    fn area(&self) -> f64 {
+        RotatedRect::area(self)
+    }
+
+    /// Computed exactly by clipping one rectangle against the other.
+    fn intersection_area(&self, other: &Self) -> f64 {
+        let a = self.corners().map(|p| (p.x, p.y));
+        let b = other.corners().map(|p| (p.x, p.y));
+        convex_intersection_area(&a, &b)
+    }
+
+    fn enclosing_area(&self, other: &Self) -> f64 {
+        let corners: Vec<Point<f64>> = self.corners().into_iter().chain(other.corners()).collect();
+        contour_area(&convex_hull(corners))
+    }
+}
+
+/// How [`soft_non_max_suppression`] decays the score of a box overlapping a selected one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoftNmsDecay {
+    /// Multiply the score by `1 - IoU` when the IoU is at least `iou_threshold`.
+    Linear { iou_threshold: f64 },
+    /// Multiply the score by `exp(-IoU² / sigma)`. `sigma` must be positive and finite.
+    Gaussian { sigma: f64 },
+}
+
+/// Greedy non-maximum suppression.
+///
+/// Repeatedly selects the highest-scoring remaining box and discards every remaining box
+/// whose IoU with it is greater than `iou_threshold`. Equal scores are broken by the lower
+/// index, so the result is deterministic.
+///
+/// # Arguments
+///
+/// * `boxes` - The candidate boxes, either `Rect`s or [`RotatedRect`]s.
+/// * `scores` - One confidence score per box.
+/// * `iou_threshold` - Boxes overlapping a selected box by more than this are suppressed.
+///
+/// # Returns
+///
+/// The indices of the kept boxes, in descending score order.
+///
+/// # Panics
+///
+/// Panics if `boxes` and `scores` have different lengths.
+pub fn non_max_suppression<B: Overlap>(
+    boxes: &[B],
+    scores: &[f64],
+    iou_threshold: f64,
+) -> Vec<usize> {
+    assert_eq!(
+        boxes.len(),
+        scores.len(),
+        "boxes and scores must have the same length"
+    );
+
+    let mut kept: Vec<usize> = Vec::new();
+    for candidate in descending_score_order(scores) {
+        if kept
+            .iter()
+            .all(|&selected| boxes[selected].iou(&boxes[candidate]) <= iou_threshold)
+        {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+/// Soft non-maximum suppression (Bodla et al., 2017).
+///
+/// Instead of discarding boxes that overlap a selected box, their scores are decayed
+/// according to `decay`. Boxes whose score falls below `score_threshold` are dropped.
+///
+/// # Returns
+///
+/// The kept boxes as `(index, decayed score)`, in the order they were selected.
+///
+/// # Panics
+///
+/// Panics if `boxes` and `scores` have different lengths, or if the `sigma` of
+/// [`SoftNmsDecay::Gaussian`] is not positive and finite.
+pub fn soft_non_max_suppression<B: Overlap>(
+    boxes: &[B],
+    scores: &[f64],
+    decay: SoftNmsDecay,
+    score_threshold: f64,
+) -> Vec<(usize, f64)> {
+    assert_eq!(
+        boxes.len(),
+        scores.len(),
+        "boxes and scores must have the same length"
+    );
+    if let SoftNmsDecay::Gaussian { sigma } = decay {
+        assert!(
+            sigma > 0.0 && sigma.is_finite(),
+            "sigma must be positive and finite, got {sigma}"
+        );
+    }
+
+    let mut remaining: Vec<(usize, f64)> = scores
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, score)| score >= score_threshold)
+        .collect();
+    let mut kept = Vec::new();
+
+    while !remaining.is_empty() {
+        let best = (0..remaining.len())
+            .max_by(|&a, &b| {
+                remaining[a]
+                    .1
+                    .total_cmp(&remaining[b].1)
+                    .then(remaining[b].0.cmp(&remaining[a].0))
+            })
+            .expect("remaining is not empty");
+        let (selected, score) = remaining.swap_remove(best);
+        kept.push((selected, score));
+
+        for (index, score) in remaining.iter_mut() {
+            let iou = boxes[selected].iou(&boxes[*index]);
+            *score *= match decay {
+                SoftNmsDecay::Linear { iou_threshold } if iou >= iou_threshold => 1.0 - iou,
+                SoftNmsDecay::Linear { .. } => 1.0,
+                SoftNmsDecay::Gaussian { sigma } => (-(iou * iou) / sigma).exp(),
+            };
+        }
+        remaining.retain(|&(_, score)| score >= score_threshold);
+    }
+
+    kept
+}
+
+/// Indices sorted by descending score, ties broken by ascending index.
+pub(crate) fn descending_score_order(scores: &[f64]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    order
+}
+
+/// When [`merge_boxes`] considers two boxes to belong to the same object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeCriterion {
+    /// The boxes overlap with an IoU greater than the given threshold. `Iou(0.0)` merges
+    /// any two boxes that share some area.
+    Iou(f64),
+    /// The boxes are at most the given number of pixels apart, measured as the larger of
+    /// the horizontal and vertical gaps between them. `Gap(0)` merges boxes that overlap or
+    /// touch.
+    Gap(u32),
+}
+
+/// The result of [`merge_boxes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedBoxes {
+    /// One bounding box per cluster, enclosing all of its members.
+    pub boxes: Vec<Rect>,
+    /// The cluster index of every input box, i.e. an index into `boxes`.
+    pub clusters: Vec<usize>,
+}
+
+impl MergedBoxes {
+    /// The indices of the input boxes that make up cluster `cluster`.
+    pub fn members(&self, cluster: usize) -> Vec<usize> {
+        self.clusters
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c == cluster)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Merges overlapping or nearly adjacent boxes into one box per object.
+///
+/// Every pair of boxes is tested with `criterion`, and matching pairs are joined with a
+/// union-find structure. The clustering is therefore transitive (single linkage): if A
+/// matches B and B matches C, all three end up in one cluster even if A and C are far apart.
+/// Pairs are tested on the input boxes, not on the growing merged boxes.
+///
+/// Clusters are numbered in order of their first member, so the output is deterministic.
+///
+/// # Returns
+///
+/// The merged boxes together with the mapping from input indices to clusters.
+pub fn merge_boxes(boxes: &[Rect], criterion: MergeCriterion) -> MergedBoxes {
+    let mut sets = UnionFind::new(boxes.len());
+    for i in 0..boxes.len() {
+        for j in (i + 1)..boxes.len() {
+            let matches = match criterion {
+                MergeCriterion::Iou(threshold) => boxes[i].iou(&boxes[j]) > threshold,
+                MergeCriterion::Gap(max_gap) => box_gap(&boxes[i], &boxes[j]) <= max_gap as u64,
+            };
+            if matches {
+                sets.union(i, j);
+            }
+        }
+    }
+
+    let (clusters, count) = sets.cluster_indices();
+    let mut merged: Vec<Option<Rect>> = vec![None; count];
+    for (b, &cluster) in boxes.iter().zip(&clusters) {
+        merged[cluster] = Some(match merged[cluster] {
+            None => *b,
+            Some(m) => {
+                let x = m.x.min(b.x);
+                let y = m.y.min(b.y);
+                let right = m.x.saturating_add(m.width).max(b.x.saturating_add(b.width));
+                let bottom =
+                    m.y.saturating_add(m.height)
+                        .max(b.y.saturating_add(b.height));
+                Rect {
+                    x,
+                    y,
+                    width: right - x,
+                    height: bottom - y,
+                }
+            }
+        });
+    }
+
+    MergedBoxes {
+        boxes: merged.into_iter().flatten().collect(),
+        clusters,
+    }
+}
+
+/// Draws the input boxes of each cluster in the cluster's own contrasting color, and the
+/// merged box of every cluster with more than one member around them.
+///
+/// # Arguments
+///
+/// * `canvas` - The image to draw on.
+/// * `boxes` - The boxes that were passed to [`merge_boxes`].
+/// * `merged` - The result of [`merge_boxes`].
+pub fn draw_box_clusters_mut(canvas: &mut RgbaImage, boxes: &[Rect], merged: &MergedBoxes) {
+    let colors = generate_contrasting_colors(merged.boxes.len(), 255);
+    let mut member_counts = vec![0usize; merged.boxes.len()];
+
+    for (b, &cluster) in boxes.iter().zip(&merged.clusters) {
+        member_counts[cluster] += 1;
+        draw_rect_outline_mut(canvas, b, colors[cluster]);
+    }
+
+    for (cluster, merged_box) in merged.boxes.iter().enumerate() {
+        if member_counts[cluster] > 1 {
+            let (left, top) = (merged_box.x as i64, merged_box.y as i64);
+            draw_frame_mut(
+                canvas,
+                (left - 1, top - 1),
+                (
+                    left + merged_box.width as i64 + 1,
+                    top + merged_box.height as i64 + 1,
+                ),
+                colors[cluster],
+            );
+        }
+    }
+}
+
+/// The larger of the horizontal and vertical gaps between two boxes, 0 if they overlap.
+fn box_gap(a: &Rect, b: &Rect) -> u64 {
+    let gap = |start_a: u32, len_a: u32, start_b: u32, len_b: u32| {
+        let (start_a, end_a) = (start_a as u64, start_a as u64 + len_a as u64);
+        let (start_b, end_b) = (start_b as u64, start_b as u64 + len_b as u64);
+        start_a.max(start_b).saturating_sub(end_a.min(end_b))
+    };
+    gap(a.x, a.width, b.x, b.width).max(gap(a.y, a.height, b.y, b.height))
+}
+
+/// Draws the outline of an `image::math::Rect`, skipping empty boxes.
+pub(crate) fn draw_rect_outline_mut(canvas: &mut RgbaImage, b: &Rect, color: Rgba<u8>) {
+    if b.width > 0 && b.height > 0 {
+        let (left, top) = (b.x as i64, b.y as i64);
+        draw_frame_mut(
+            canvas,
+            (left, top),
+            (left + b.width as i64, top + b.height as i64),
+            color,
+        );
+    }
+}
+
+/// Draws the outline of the pixels from `top_left` up to, but excluding, `bottom_right`.
+///
+/// Edges outside the canvas are not drawn, on every side alike. Coordinates are clipped to
+/// one pixel around the canvas first, so they fit in the `i32` of `imageproc`.
+fn draw_frame_mut(
+    canvas: &mut RgbaImage,
+    top_left: (i64, i64),
+    bottom_right: (i64, i64),
+    color: Rgba<u8>,
+) {
+    let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+    let (left, right) = (top_left.0.max(-1), bottom_right.0.min(width + 1));
+    let (top, bottom) = (top_left.1.max(-1), bottom_right.1.min(height + 1));
+    if left < right && top < bottom {
+        draw_hollow_rect_mut(
+            canvas,
+            imageproc::rect::Rect::at(left as i32, top as i32)
+                .of_size((right - left) as u32, (bottom - top) as u32),
+            color,
+        );
+    }
+}
+
+/// The order in which [`reading_order`] visits boxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadingOrder {
+    /// Top-to-bottom rows, left-to-right within each row.
+    RowMajor,
+    /// Left-to-right columns, top-to-bottom within each column.
+    ColumnMajor,
+    /// Like `RowMajor`, but every second row runs right-to-left (boustrophedon), as used by
+    /// plate readers and serpentine scans.
+    Serpentine,
+}
+
+/// Computes the reading order of a set of axis-aligned boxes.
+///
+/// Boxes are first clustered into rows (or columns for [`ReadingOrder::ColumnMajor`]).
+/// A box joins a row when its vertical extent overlaps that of the row member closest to it
+/// horizontally by at least `min_overlap` of the smaller of the two heights. Because each
+/// box is only compared with its horizontal neighbour, a row may drift up or down across
+/// the image, which makes the clustering tolerant of moderate skew.
+///
+/// Rows are then ordered by their mean center, and boxes within a row by their center.
+///
+/// # Arguments
+///
+/// * `boxes` - The boxes to order, e.g. from `to_axis_aligned_bounding_box`.
+/// * `order` - The traversal order.
+/// * `min_overlap` - The required overlap ratio, in `(0, 1]`. `0.5` is a good default.
+///
+/// # Returns
+///
+/// A permutation of `0..boxes.len()`: the first element is the index of the first box to
+/// read, and so on.
+///
+/// # Panics
+///
+/// Panics if `min_overlap` is not in `(0, 1]`.
+pub fn reading_order(boxes: &[Rect], order: ReadingOrder, min_overlap: f32) -> Vec<usize> {
+    let extents: Vec<Extent> = boxes
+        .iter()
+        .map(|b| Extent {
+            min_x: b.x as f64,
+            min_y: b.y as f64,
+            max_x: b.x as f64 + b.width as f64,
+            max_y: b.y as f64 + b.height as f64,
+        })
+        .collect();
+    reading_order_of_extents(&extents, order, min_overlap)
+}
+
+/// Draws each box outline, its 1-based position in `order`, and a path joining consecutive
+/// box centers, so the sequence can be checked at a glance.
+///
+/// # Arguments
+///
+/// * `canvas` - The image to draw on.
+/// * `boxes` - The boxes that were ordered.
+/// * `order` - A permutation of box indices, typically from [`reading_order`].
+/// * `color` - The color used for outlines, labels and the path.
+pub fn draw_reading_order_mut(
+    canvas: &mut RgbaImage,
+    boxes: &[Rect],
+    order: &[usize],
+    color: Rgba<u8>,
+) {
+    let mut previous_center: Option<(f32, f32)> = None;
+
+    for (sequence, &index) in order.iter().enumerate() {
+        let b = boxes[index];
+        draw_rect_outline_mut(canvas, &b, color);
+
+        let center = (
+            b.x as f32 + b.width as f32 / 2.0,
+            b.y as f32 + b.height as f32 / 2.0,
+        );
+        if let Some(previous) = previous_center {
+            draw_line_segment_mut(canvas, previous, center, color);
+        }
+        previous_center = Some(center);
+
+        draw_number_mut(
+            canvas,
+            (b.x as i32 + 2, b.y as i32 + 2),
+            sequence + 1,
+            1,
+            color,
+        );
+    }
+}
+
+/// An axis-aligned extent in floating point, shared by the box and contour orderings.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Extent {
+    pub(crate) min_x: f64,
+    pub(crate) min_y: f64,
+    pub(crate) max_x: f64,
+    pub(crate) max_y: f64,
+}
+
+impl Extent {
+    fn transposed(self) -> Self {
+        Extent {
+            min_x: self.min_y,
+            min_y: self.min_x,
+            max_x: self.max_y,
+            max_y: self.max_x,
+        }
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (
+            (self.min_x + self.max_x) / 2.0,
+            (self.min_y + self.max_y) / 2.0,
+        )
+    }
+
+    /// The vertical overlap relative to the smaller height, in `[0, 1]`.
+    fn vertical_overlap_ratio(&self, other: &Extent) -> f64 {
+        let overlap = self.max_y.min(other.max_y) - self.min_y.max(other.min_y);
+        if overlap < 0.0 {
+            return 0.0;
+        }
+        let shorter = (self.max_y - self.min_y).min(other.max_y - other.min_y);
+        if shorter <= 0.0 {
+            1.0
+        } else {
+            (overlap / shorter).min(1.0)
+        }
+    }
+}
+
+pub(crate) fn reading_order_of_extents(
+    extents: &[Extent],
+    order: ReadingOrder,
+    min_overlap: f32,
+) -> Vec<usize> {
+    assert!(
+        min_overlap > 0.0 && min_overlap <= 1.0,
+        "min_overlap must be in (0, 1]"
+    );
+
+    // Column-major ordering is row-major ordering of the transposed layout.
+    let extents: Vec<Extent> = match order {
+        ReadingOrder::ColumnMajor => extents.iter().map(|e| e.transposed()).collect(),
+        ReadingOrder::RowMajor | ReadingOrder::Serpentine => extents.to_vec(),
+    };
+
+    let mut by_top: Vec<usize> = (0..extents.len()).collect();
+    by_top.sort_by(|&a, &b| extents[a].center().1.total_cmp(&extents[b].center().1));
+
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+    for index in by_top {
+        let extent = &extents[index];
+        let center_x = extent.center().0;
+
+        let best_row = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(row_index, row)| {
+                let neighbour = row.iter().min_by(|&&a, &&b| {
+                    let da = (extents[a].center().0 - center_x).abs();
+                    let db = (extents[b].center().0 - center_x).abs();
+                    da.total_cmp(&db)
+                })?;
+                let ratio = extent.vertical_overlap_ratio(&extents[*neighbour]);
+                (ratio >= min_overlap as f64).then_some((row_index, ratio))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best_row {
+            Some((row_index, _)) => rows[row_index].push(index),
+            None => rows.push(vec![index]),
+        }
+    }
+
+    let mean_center_y = |row: &Vec<usize>| {
+        row.iter().map(|&i| extents[i].center().1).sum::<f64>() / row.len() as f64
+    };
+    rows.sort_by(|a, b| mean_center_y(a).total_cmp(&mean_center_y(b)));
+
+    let mut result = Vec::with_capacity(extents.len());
+    for (row_index, mut row) in rows.into_iter().enumerate() {
+        row.sort_by(|&a, &b| extents[a].center().0.total_cmp(&extents[b].center().0));
+        if order == ReadingOrder::Serpentine && row_index % 2 == 1 {
+            row.reverse();
+        }
+        result.extend(row);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{cell, rect};
     use imageproc::point::Point;
 
     #[test]
@@ -155,6 +1323,66 @@ mod tests {
         assert_eq!(to_axis_aligned_bounding_box(&vertices), expected);
     }
 
+    #[test]
+    fn test_bounding_rects_match_min_area_rect_path() {
+        let contours = vec![
+            Contour::new(
+                vec![
+                    Point::new(2, 5),
+                    Point::new(16, 5),
+                    Point::new(30, 5),
+                    Point::new(30, 40),
+                    Point::new(2, 40),
+                ],
+                imageproc::contours::BorderType::Outer,
+                None,
+            ),
+            Contour::new(
+                vec![Point::new(-5, 3), Point::new(8, 9)],
+                imageproc::contours::BorderType::Hole,
+                Some(0),
+            ),
+            Contour::new(Vec::new(), imageproc::contours::BorderType::Outer, None),
+        ];
+
+        let rects = bounding_rects(&contours);
+        let upright = rects[0].unwrap();
+        let via_min_area_rect = to_axis_aligned_bounding_box(&min_area_rect(&contours[0].points));
+        assert_eq!(
+            (
+                upright.left(),
+                upright.top(),
+                upright.width(),
+                upright.height()
+            ),
+            (
+                via_min_area_rect.x as i32,
+                via_min_area_rect.y as i32,
+                via_min_area_rect.width,
+                via_min_area_rect.height
+            )
+        );
+        assert_eq!(rects[1].map(|r| (r.left(), r.width())), Some((-5, 13)));
+        assert_eq!(rects[2], None);
+        for (contour, rect) in contours.iter().zip(&rects) {
+            assert_eq!(bounding_rect(&contour.points), *rect);
+        }
+
+        let clipped = clipped_bounding_rects(&contours, 20, 20);
+        assert_eq!(
+            clipped[0].map(|r| (r.x, r.width, r.height)),
+            Some((2, 18, 15))
+        );
+        assert_eq!(clipped[1].map(|r| (r.x, r.width)), Some((0, 8)));
+        assert_eq!(clipped[2], None);
+
+        #[cfg(feature = "rayon")]
+        {
+            assert_eq!(par_bounding_rects(&contours), rects);
+            assert_eq!(par_clipped_bounding_rects(&contours, 20, 20), clipped);
+        }
+    }
+
     #[test]
     fn test_single_point_rect() {
         // A degenerate rectangle where all points are the same.
@@ -172,4 +1400,498 @@ mod tests {
         };
         assert_eq!(to_axis_aligned_bounding_box(&vertices), expected);
     }
+
+    #[test]
+    fn test_reading_order_of_skewed_grid() {
+        // A 2x3 grid where each row drifts downwards by 4 pixels per column,
+        // shuffled so that input order carries no information.
+        let boxes = vec![
+            cell(40, 28), // row 1, col 2
+            cell(0, 0),   // row 0, col 0
+            cell(20, 24), // row 1, col 1
+            cell(40, 8),  // row 0, col 2
+            cell(0, 20),  // row 1, col 0
+            cell(20, 4),  // row 0, col 1
+        ];
+
+        assert_eq!(
+            reading_order(&boxes, ReadingOrder::RowMajor, 0.5),
+            vec![1, 5, 3, 4, 2, 0]
+        );
+        assert_eq!(
+            reading_order(&boxes, ReadingOrder::Serpentine, 0.5),
+            vec![1, 5, 3, 0, 2, 4]
+        );
+        assert_eq!(
+            reading_order(&boxes, ReadingOrder::ColumnMajor, 0.5),
+            vec![1, 4, 5, 2, 3, 0]
+        );
+    }
+
+    #[test]
+    fn test_reading_order_empty_and_single() {
+        assert!(reading_order(&[], ReadingOrder::RowMajor, 0.5).is_empty());
+        assert_eq!(
+            reading_order(&[cell(3, 3)], ReadingOrder::Serpentine, 1.0),
+            vec![0]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "min_overlap must be in (0, 1]")]
+    fn test_reading_order_rejects_zero_overlap() {
+        reading_order(&[cell(0, 0)], ReadingOrder::RowMajor, 0.0);
+    }
+
+    #[test]
+    fn test_draw_reading_order() {
+        let background = Rgba([0, 0, 0, 255]);
+        let color = Rgba([255, 255, 0, 255]);
+        let mut canvas = RgbaImage::from_pixel(40, 20, background);
+        let boxes = [cell(0, 0), cell(20, 0)];
+
+        draw_reading_order_mut(&mut canvas, &boxes, &[0, 1], color);
+
+        // Box outline.
+        assert_eq!(*canvas.get_pixel(0, 0), color);
+        // The "1" glyph of the first box: its top row is 010.
+        assert_eq!(*canvas.get_pixel(2, 2), background);
+        assert_eq!(*canvas.get_pixel(3, 2), color);
+        // The "2" glyph of the second box: its top row is 111.
+        assert_eq!(*canvas.get_pixel(22, 2), color);
+        // The path between the two centers.
+        assert_eq!(*canvas.get_pixel(15, 5), color);
+    }
+
+    #[test]
+    fn test_signed_bounding_box_keeps_negative_extents() {
+        let vertices = [
+            Point { x: -10.0, y: -20.0 },
+            Point { x: 50.0, y: 30.0 },
+            Point { x: 50.0, y: -20.0 },
+            Point { x: -10.0, y: 30.0 },
+        ];
+        let expected = imageproc::rect::Rect::at(-10, -20).of_size(60, 50);
+        assert_eq!(to_signed_bounding_box(&vertices), Some(expected));
+
+        // Fractional coordinates are snapped outwards.
+        let points = [Point::new(0.5, 1.5), Point::new(2.2, 3.0)];
+        assert_eq!(
+            to_signed_bounding_box(&points),
+            Some(imageproc::rect::Rect::at(0, 1).of_size(3, 2))
+        );
+
+        // A single point still yields a one-pixel box.
+        assert_eq!(
+            to_signed_bounding_box(&[Point::new(7, 8)]),
+            Some(imageproc::rect::Rect::at(7, 8).of_size(1, 1))
+        );
+    }
+
+    #[test]
+    fn test_signed_bounding_box_rejects_invalid_input() {
+        let empty: [Point<f32>; 0] = [];
+        assert_eq!(to_signed_bounding_box(&empty), None);
+        assert_eq!(
+            to_signed_bounding_box(&[Point::new(f64::NAN, 1.0), Point::new(2.0, 3.0)]),
+            None
+        );
+        assert_eq!(
+            to_signed_bounding_box(&[Point::new(0.0, 0.0), Point::new(1e12, 3.0)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clipped_bounding_box() {
+        let points = [Point::new(-10, -20), Point::new(50, 30)];
+        assert_eq!(
+            to_clipped_bounding_box(&points, 40, 100),
+            Some(Rect {
+                x: 0,
+                y: 0,
+                width: 40,
+                height: 30,
+            })
+        );
+
+        // Entirely to the right of the image.
+        let outside = [Point::new(120, 10), Point::new(130, 20)];
+        assert_eq!(to_clipped_bounding_box(&outside, 100, 100), None);
+
+        // Touching the right edge from the outside.
+        let touching = [Point::new(100, 10), Point::new(110, 20)];
+        assert_eq!(to_clipped_bounding_box(&touching, 100, 100), None);
+    }
+
+    fn assert_point_near(actual: Point<f64>, expected: (f64, f64)) {
+        assert!(
+            (actual.x - expected.0).abs() < 1e-9 && (actual.y - expected.1).abs() < 1e-9,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn test_rotated_rect_from_axis_aligned_corners() {
+        let corners = [
+            Point::new(20, 30),
+            Point::new(120, 30),
+            Point::new(120, 80),
+            Point::new(20, 80),
+        ];
+        let rect = RotatedRect::from_corners(&corners);
+
+        assert_eq!(rect.center, Point::new(70.0, 55.0));
+        assert_eq!((rect.width, rect.height, rect.angle), (100.0, 50.0, 0.0));
+        assert_eq!(rect.area(), 5000.0);
+        assert_eq!(rect.aspect_ratio(), 2.0);
+        assert_eq!(
+            rect.to_axis_aligned_bounding_box(),
+            to_axis_aligned_bounding_box(&corners)
+        );
+        let round_trip: [Point<f64>; 4] = rect.into();
+        for (actual, expected) in round_trip.into_iter().zip(corners) {
+            assert_point_near(actual, (expected.x as f64, expected.y as f64));
+        }
+    }
+
+    #[test]
+    fn test_rotated_rect_canonical_angle() {
+        // The diamond from `test_bounding_box_for_rotated_rect`: a square rotated by 45°.
+        let diamond = [
+            Point::new(50.0, 10.0),
+            Point::new(90.0, 50.0),
+            Point::new(50.0, 90.0),
+            Point::new(10.0, 50.0),
+        ];
+        let rect = RotatedRect::from(diamond);
+        assert!((rect.angle + 45.0).abs() < 1e-9, "angle was {}", rect.angle);
+        assert!((rect.width - 40.0 * 2f64.sqrt()).abs() < 1e-9);
+        assert_eq!(rect.to_axis_aligned_bounding_box().width, 80);
+
+        // Any multiple of 90° describes the same rectangle with swapped sides.
+        let a = RotatedRect::new(Point::new(0.0, 0.0), 10.0, 4.0, 30.0);
+        let b = RotatedRect::new(Point::new(0.0, 0.0), 4.0, 10.0, 120.0);
+        let c = RotatedRect::new(Point::new(0.0, 0.0), 10.0, 4.0, -150.0);
+        assert!((a.angle - b.angle).abs() < 1e-9 && (a.angle - c.angle).abs() < 1e-9);
+        assert_eq!((a.width, a.height), (b.width, b.height));
+        assert_eq!((a.width, a.height), (c.width, c.height));
+
+        let b = RotatedRect::new(Point::new(0.0, 0.0), 4.0, 10.0, 60.0);
+        assert!((b.angle + 30.0).abs() < 1e-9);
+        assert_eq!((b.width, b.height), (10.0, 4.0));
+    }
+
+    #[test]
+    fn test_rotated_rect_contains() {
+        let rect = RotatedRect::new(Point::new(10.0, 10.0), 20.0, 2.0, 30.0);
+        assert!(rect.contains(Point::new(10, 10)));
+        // Along the long axis, 9 units away from the center.
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        assert!(rect.contains(Point::new(10.0 + 9.0 * cos, 10.0 + 9.0 * sin)));
+        // The same distance along the short axis is outside.
+        assert!(!rect.contains(Point::new(10.0 - 9.0 * sin, 10.0 + 9.0 * cos)));
+        for corner in rect.corners() {
+            assert!(rect.contains(corner));
+        }
+    }
+
+    #[test]
+    fn test_rotated_rect_enclosing_points() {
+        let points = [
+            Point::new(0, 0),
+            Point::new(10, 0),
+            Point::new(10, 4),
+            Point::new(0, 4),
+            Point::new(5, 2),
+        ];
+        let rect = RotatedRect::enclosing(&points);
+        assert_eq!((rect.width, rect.height), (10.0, 4.0));
+        assert_eq!(rect.aspect_ratio(), 2.5);
+        assert!(points.iter().all(|&p| rect.contains(p)));
+
+        let line = RotatedRect::new(Point::new(0.0, 0.0), 5.0, 0.0, 0.0);
+        assert_eq!(line.aspect_ratio(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_rect_iou_and_giou() {
+        let a = cell(0, 0);
+        let b = Rect {
+            x: 5,
+            y: 0,
+            width: 10,
+            height: 10,
+        };
+        // Intersection 50, union 150, enclosing 150.
+        assert!((a.iou(&b) - 1.0 / 3.0).abs() < 1e-12);
+        assert!((a.giou(&b) - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(a.iou(&a), 1.0);
+        assert_eq!(a.giou(&a), 1.0);
+
+        // Disjoint boxes: IoU is 0, GIoU becomes negative with distance.
+        let near = cell(20, 0);
+        let far = cell(40, 0);
+        assert_eq!(a.iou(&near), 0.0);
+        assert!(a.giou(&near) < 0.0);
+        assert!(a.giou(&far) < a.giou(&near));
+
+        let empty = Rect {
+            x: 3,
+            y: 3,
+            width: 0,
+            height: 0,
+        };
+        assert_eq!(empty.iou(&empty), 0.0);
+    }
+
+    #[test]
+    fn test_rotated_rect_iou() {
+        let a = RotatedRect::new(Point::new(0.0, 0.0), 2.0, 2.0, 0.0);
+        let b = RotatedRect::new(Point::new(1.0, 1.0), 2.0, 2.0, 0.0);
+        assert!((a.iou(&b) - 1.0 / 7.0).abs() < 1e-12);
+        assert!((a.iou(&a) - 1.0).abs() < 1e-12);
+
+        // A square and the same square rotated by 45°: the intersection is a regular
+        // octagon with area 8 * (sqrt(2) - 1) for a square of side 2.
+        let c = RotatedRect::new(Point::new(0.0, 0.0), 2.0, 2.0, -45.0);
+        let octagon = 8.0 * (2f64.sqrt() - 1.0);
+        assert!((a.intersection_area(&c) - octagon).abs() < 1e-9);
+        assert!((a.iou(&c) - octagon / (8.0 - octagon)).abs() < 1e-9);
+
+        let far = RotatedRect::new(Point::new(10.0, 0.0), 2.0, 2.0, 30.0);
+        assert_eq!(a.iou(&far), 0.0);
+        assert!(a.giou(&far) < 0.0);
+    }
+
+    #[test]
+    fn test_non_max_suppression() {
+        let boxes = [
+            cell(0, 0),
+            Rect {
+                x: 1,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+            cell(30, 30),
+            cell(0, 1),
+        ];
+        let scores = [0.8, 0.9, 0.5, 0.9];
+
+        // Boxes 1 and 3 tie; the lower index wins, and 3 is then suppressed by 1.
+        assert_eq!(non_max_suppression(&boxes, &scores, 0.5), vec![1, 2]);
+        // With a permissive threshold nothing is suppressed.
+        assert_eq!(non_max_suppression(&boxes, &scores, 1.0), vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_soft_non_max_suppression() {
+        let boxes = [
+            cell(0, 0),
+            Rect {
+                x: 5,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+            cell(30, 30),
+        ];
+        let scores = [0.9, 0.8, 0.3];
+
+        let kept = soft_non_max_suppression(
+            &boxes,
+            &scores,
+            SoftNmsDecay::Linear { iou_threshold: 0.3 },
+            0.1,
+        );
+        // Box 1 overlaps box 0 with IoU 1/3, so its score decays to 0.8 * 2/3.
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0], (0, 0.9));
+        assert_eq!(kept[1].0, 1);
+        assert!((kept[1].1 - 0.8 * 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(kept[2], (2, 0.3));
+
+        let kept =
+            soft_non_max_suppression(&boxes, &scores, SoftNmsDecay::Gaussian { sigma: 0.5 }, 0.6);
+        // exp(-(1/9) / 0.5) * 0.8 ≈ 0.64 stays above the threshold, box 2 does not.
+        assert_eq!(kept.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "sigma must be positive and finite")]
+    fn test_soft_non_max_suppression_rejects_zero_sigma() {
+        soft_non_max_suppression(
+            &[cell(0, 0)],
+            &[0.9],
+            SoftNmsDecay::Gaussian { sigma: 0.0 },
+            0.1,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "boxes and scores must have the same length")]
+    fn test_non_max_suppression_length_mismatch() {
+        non_max_suppression(&[cell(0, 0)], &[], 0.5);
+    }
+
+    #[test]
+    fn test_merge_boxes_by_gap() {
+        let boxes = [
+            cell(0, 0),
+            cell(12, 0),  // 2 px right of box 0
+            cell(50, 50), // isolated
+            cell(24, 3),  // 2 px right of box 1, far from box 0
+        ];
+
+        let merged = merge_boxes(&boxes, MergeCriterion::Gap(2));
+        assert_eq!(merged.clusters, vec![0, 0, 1, 0]);
+        assert_eq!(
+            merged.boxes,
+            vec![
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 34,
+                    height: 13,
+                },
+                cell(50, 50),
+            ]
+        );
+        assert_eq!(merged.members(0), vec![0, 1, 3]);
+
+        let merged = merge_boxes(&boxes, MergeCriterion::Gap(1));
+        assert_eq!(merged.clusters, vec![0, 1, 2, 3]);
+        assert_eq!(merged.boxes, boxes.to_vec());
+    }
+
+    #[test]
+    fn test_merge_boxes_by_iou() {
+        let boxes = [
+            cell(0, 0),
+            cell(10, 0), // touches box 0, IoU 0
+            cell(2, 0),  // IoU 8/12 with box 0
+        ];
+        let merged = merge_boxes(&boxes, MergeCriterion::Iou(0.5));
+        assert_eq!(merged.clusters, vec![0, 1, 0]);
+        let merged = merge_boxes(&boxes, MergeCriterion::Iou(0.0));
+        assert_eq!(merged.clusters, vec![0, 0, 0]);
+        assert!(merge_boxes(&[], MergeCriterion::Iou(0.0)).boxes.is_empty());
+    }
+
+    #[test]
+    fn test_draw_box_clusters() {
+        let boxes = [cell(2, 2), cell(14, 2), cell(40, 2)];
+        let merged = merge_boxes(&boxes, MergeCriterion::Gap(2));
+        let background = Rgba([0, 0, 0, 255]);
+        let mut canvas = RgbaImage::from_pixel(60, 20, background);
+
+        draw_box_clusters_mut(&mut canvas, &boxes, &merged);
+
+        let colors = generate_contrasting_colors(2, 255);
+        assert_eq!(*canvas.get_pixel(2, 2), colors[0]);
+        assert_eq!(*canvas.get_pixel(14, 2), colors[0]);
+        assert_eq!(*canvas.get_pixel(40, 2), colors[1]);
+        // The merged outline of the first cluster, one pixel outside its members.
+        assert_eq!(*canvas.get_pixel(1, 1), colors[0]);
+        assert_eq!(*canvas.get_pixel(39, 1), background);
+
+        // At the canvas corner the frame keeps its size; only the edges off the canvas go.
+        let corner = [rect(0, 0, 3, 3), rect(2, 2, 3, 3)];
+        let merged = merge_boxes(&corner, MergeCriterion::Gap(0));
+        let mut canvas = RgbaImage::from_pixel(10, 10, background);
+        draw_box_clusters_mut(&mut canvas, &corner, &merged);
+        let color = generate_contrasting_colors(1, 255)[0];
+        assert_eq!(*canvas.get_pixel(5, 3), color);
+        assert_eq!(*canvas.get_pixel(3, 5), color);
+        assert_eq!(*canvas.get_pixel(6, 3), background);
+        assert_eq!(*canvas.get_pixel(3, 6), background);
+    }
+
+    #[test]
+    fn test_merge_and_draw_boxes_near_u32_max() {
+        let far = u32::MAX - 5;
+        let boxes = [rect(far, far, 10, 10), rect(far - 2, far - 2, 4, 4)];
+        let merged = merge_boxes(&boxes, MergeCriterion::Gap(0));
+        assert_eq!(merged.boxes, vec![rect(far - 2, far - 2, 7, 7)]);
+
+        let mut canvas = RgbaImage::new(4, 4);
+        draw_box_clusters_mut(&mut canvas, &boxes, &merged);
+        assert!(canvas.pixels().all(|p| p.0 == [0; 4]));
+    }
+
+    #[test]
+    fn test_pad_and_scale() {
+        assert_eq!(pad(rect(10, 10, 20, 10), 5), rect(5, 5, 30, 20));
+        // Growth past the top-left corner is cut off.
+        assert_eq!(pad(rect(2, 3, 10, 10), 5), rect(0, 0, 17, 18));
+
+        assert_eq!(pad_relative(rect(10, 10, 20, 10), 0.1), rect(8, 9, 24, 12));
+        // 0.25 * 10 = 2.5 on each side, rounded outwards.
+        assert_eq!(pad_relative(rect(10, 10, 10, 10), 0.25), rect(7, 7, 16, 16));
+
+        assert_eq!(
+            scale_about_center(rect(10, 10, 20, 10), 2.0),
+            rect(0, 5, 40, 20)
+        );
+        assert_eq!(
+            scale_about_center(rect(10, 10, 20, 10), 0.5),
+            rect(15, 12, 10, 6)
+        );
+        assert_eq!(
+            scale_about_center(rect(10, 10, 20, 10), 1.0),
+            rect(10, 10, 20, 10)
+        );
+    }
+
+    #[test]
+    fn test_expand_to_aspect_ratio() {
+        // A tall box becomes square.
+        assert_eq!(
+            expand_to_aspect_ratio(rect(20, 10, 10, 30), 1.0),
+            rect(10, 10, 30, 30)
+        );
+        // A wide box grows vertically to 2:1.
+        assert_eq!(
+            expand_to_aspect_ratio(rect(0, 20, 40, 10), 2.0),
+            rect(0, 15, 40, 20)
+        );
+        // Already matching.
+        assert_eq!(
+            expand_to_aspect_ratio(rect(5, 5, 16, 9), 16.0 / 9.0),
+            rect(5, 5, 16, 9)
+        );
+    }
+
+    #[test]
+    fn test_clamp_to_image() {
+        assert_eq!(
+            clamp_to_image(rect(90, 10, 20, 20), 100, 100),
+            Some(rect(90, 10, 10, 20))
+        );
+        assert_eq!(
+            clamp_to_image(rect(10, 10, 20, 20), 100, 100),
+            Some(rect(10, 10, 20, 20))
+        );
+        assert_eq!(clamp_to_image(rect(100, 10, 20, 20), 100, 100), None);
+        assert_eq!(clamp_to_image(rect(10, 10, 0, 20), 100, 100), None);
+    }
+
+    #[test]
+    fn test_resize_and_letterbox_transforms() {
+        let resize = ResizeTransform::resize((200, 100), (100, 100));
+        assert_eq!(resize.apply(rect(20, 10, 40, 20)), rect(10, 10, 20, 20));
+        assert_eq!(resize.invert(rect(10, 10, 20, 20)), rect(20, 10, 40, 20));
+
+        // 200x100 into 100x100: scale 0.5, 25 px of padding above and below.
+        let letterbox = ResizeTransform::letterbox((200, 100), (100, 100));
+        assert_eq!(letterbox.scale, (0.5, 0.5));
+        assert_eq!(letterbox.offset, (0.0, 25.0));
+        let mapped = letterbox.apply(rect(20, 10, 40, 20));
+        assert_eq!(mapped, rect(10, 30, 20, 10));
+        assert_eq!(letterbox.invert(mapped), rect(20, 10, 40, 20));
+
+        // A detection reaching into the top padding maps back cut off at 0.
+        assert_eq!(letterbox.invert(rect(0, 20, 10, 10)), rect(0, 0, 20, 10));
+    }
 }
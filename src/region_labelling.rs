@@ -1,51 +1,1285 @@
-use crate::colors::generate_contrasting_colors;
-use image::{ImageBuffer, Luma, Rgba, RgbaImage};
-use std::collections::HashMap;
+use crate::colors::{color_for_id, generate_contrasting_colors};
+use image::{GrayImage, ImageBuffer, Luma, Pixel, Primitive, Rgba, RgbaImage, math::Rect};
+use imageproc::{
+    contours::{Contour, find_contours},
+    drawing::{draw_cross_mut, draw_line_segment_mut},
+};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+    ops::RangeBounds,
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+pub use crate::watershed::{MarkerMethod, SplitDebug, split_touching, split_touching_debug};
+
+/// The unsigned integer types that can hold labels: `u8`, `u16`, `u32` and `u64`.
+///
+/// Every function in this module accepts label images of any of these types, so 8-bit
+/// masks or 16-bit label images from other tools can be used without converting them to
+/// `u32` first.
+pub trait Label: Primitive + Ord + Hash + Into<u64> + Send + Sync {}
+
+impl Label for u8 {}
+impl Label for u16 {}
+impl Label for u32 {}
+impl Label for u64 {}
+
+/// Per-label measurements of a labelled image, as returned by [`component_stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStats<L = u32> {
+    /// The label of the component.
+    pub label: L,
+    /// The number of pixels with this label.
+    pub area: u64,
+    /// The smallest box containing every pixel of the component.
+    pub bounding_box: Rect,
+    /// The mean `(x, y)` of the component's pixels.
+    pub centroid: (f64, f64),
+    /// The first pixel of the component in row-major order, useful as a seed for flood
+    /// fills or to identify the component across relabellings.
+    pub first_pixel: (u32, u32),
+    /// Perimeter and hole count, only computed by [`component_stats_with_shape`].
+    pub shape: Option<ShapeStats>,
+}
+
+/// The more expensive measurements of [`component_stats_with_shape`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeStats {
+    /// The number of pixel edges separating the component from other labels or from the
+    /// image border (the "crack" perimeter).
+    pub perimeter: u64,
+    /// The number of 4-connected regions of other labels that are completely enclosed by
+    /// the component. Enclosed components with their own label count as holes too.
+    pub holes: usize,
+}
+
+/// Computes area, bounding box, centroid and first pixel of every component in one pass.
+///
+/// This is the equivalent of OpenCV's `connectedComponentsWithStats`, and the starting
+/// point for filtering, ranking and reporting components.
+///
+/// # Arguments
+/// * `labelled_image` - The labelled image generated by a function like `imageproc::region_labelling::connected_components`.
+/// * `background_label` - The label of background pixels, which are not a component.
+///
+/// # Returns
+/// One entry per label present in the image, sorted by label.
+pub fn component_stats<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+) -> Vec<ComponentStats<L>> {
+    struct Accumulator {
+        area: u64,
+        min: (u32, u32),
+        max: (u32, u32),
+        sum: (f64, f64),
+        first_pixel: (u32, u32),
+    }
+
+    let mut accumulators: BTreeMap<L, Accumulator> = BTreeMap::new();
+    for (x, y, pixel) in labelled_image.enumerate_pixels() {
+        let label = pixel[0];
+        if label == background_label {
+            continue;
+        }
+        let accumulator = accumulators.entry(label).or_insert(Accumulator {
+            area: 0,
+            min: (x, y),
+            max: (x, y),
+            sum: (0.0, 0.0),
+            first_pixel: (x, y),
+        });
+        accumulator.area += 1;
+        accumulator.min = (accumulator.min.0.min(x), accumulator.min.1.min(y));
+        accumulator.max = (accumulator.max.0.max(x), accumulator.max.1.max(y));
+        accumulator.sum = (accumulator.sum.0 + x as f64, accumulator.sum.1 + y as f64);
+    }
+
+    accumulators
+        .into_iter()
+        .map(|(label, a)| ComponentStats {
+            label,
+            area: a.area,
+            bounding_box: Rect {
+                x: a.min.0,
+                y: a.min.1,
+                width: a.max.0 - a.min.0 + 1,
+                height: a.max.1 - a.min.1 + 1,
+            },
+            centroid: (a.sum.0 / a.area as f64, a.sum.1 / a.area as f64),
+            first_pixel: a.first_pixel,
+            shape: None,
+        })
+        .collect()
+}
+
+/// Like [`component_stats`], but also fills in [`ComponentStats::shape`].
+///
+/// The perimeter costs one more pass over the image. Holes are found by flood filling the
+/// bounding box of each component, so this is slower for large, nested components.
+pub fn component_stats_with_shape<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+) -> Vec<ComponentStats<L>> {
+    let mut stats = component_stats(labelled_image, background_label);
+    let (width, height) = labelled_image.dimensions();
+
+    let mut perimeters: HashMap<L, u64> = HashMap::new();
+    for (x, y, pixel) in labelled_image.enumerate_pixels() {
+        let label = pixel[0];
+        if label == background_label {
+            continue;
+        }
+        let differs = |nx: i64, ny: i64| {
+            nx < 0
+                || ny < 0
+                || nx >= width as i64
+                || ny >= height as i64
+                || labelled_image.get_pixel(nx as u32, ny as u32)[0] != label
+        };
+        let (x, y) = (x as i64, y as i64);
+        let edges = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+            .into_iter()
+            .filter(|&(nx, ny)| differs(nx, ny))
+            .count() as u64;
+        *perimeters.entry(label).or_insert(0) += edges;
+    }
+
+    for component in &mut stats {
+        component.shape = Some(ShapeStats {
+            perimeter: perimeters[&component.label],
+            holes: count_holes(labelled_image, component.label, component.bounding_box),
+        });
+    }
+    stats
+}
+
+/// Counts the 4-connected regions of pixels not labelled `label` inside `bounding_box`
+/// that cannot reach the outside of the box.
+fn count_holes<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    label: L,
+    bounding_box: Rect,
+) -> usize {
+    // The box with a one pixel frame, so that everything outside is one connected region.
+    let width = bounding_box.width as usize + 2;
+    let height = bounding_box.height as usize + 2;
+    let mut blocked = vec![false; width * height];
+    for y in 0..bounding_box.height {
+        for x in 0..bounding_box.width {
+            let pixel = labelled_image.get_pixel(bounding_box.x + x, bounding_box.y + y);
+            blocked[(y as usize + 1) * width + x as usize + 1] = pixel[0] == label;
+        }
+    }
+
+    let fill = |start: usize, blocked: &mut [bool]| {
+        let mut stack = vec![start];
+        blocked[start] = true;
+        while let Some(index) = stack.pop() {
+            let (x, y) = (index % width, index / width);
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !blocked[neighbor] {
+                    blocked[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+    };
+
+    fill(0, &mut blocked);
+    let mut holes = 0;
+    for index in 0..blocked.len() {
+        if !blocked[index] {
+            fill(index, &mut blocked);
+            holes += 1;
+        }
+    }
+    holes
+}
+
+/// How [`draw_principal_connected_components_with_coloring`] assigns colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentColoring {
+    /// Contrasting colors by size rank: the largest component gets the first color. Colors
+    /// change when `n` or the ranking changes.
+    ByRank,
+    /// A color derived from the label alone, so a component keeps its color when `n`
+    /// changes or across frames that share labels.
+    ByLabel,
+}
 
 /// Draws the n largest connected components with contrasting colors.
 ///
+/// Components of equal size are ranked by ascending label, so the output is the same on
+/// every run. Small labels are counted in a dense table instead of a hash map, and with the
+/// `rayon` feature counting and painting run in parallel.
+///
 /// # Arguments
 /// * `labelled_image` - The labelled image generated by a function like `imageproc::region_labelling::connected_components`.
+///   Label 0 is the background and is never colored.
 /// * `n` - The number of largest components to keep and color.
 /// * `background_color` - The color for the background and smaller, unselected components.
 ///
 /// # Returns
 /// An `RgbaImage` where the `n` largest components are colored and the rest is background.
-pub fn draw_principal_connected_components(
-    labelled_image: &ImageBuffer<Luma<u32>, Vec<u32>>,
+pub fn draw_principal_connected_components<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
     n: usize,
     background_color: Rgba<u8>,
 ) -> RgbaImage {
-    let mut counts = HashMap::new();
-    for label in labelled_image.pixels() {
-        if label[0] != 0 {
-            *counts.entry(label[0]).or_insert(0) += 1;
-        }
-    }
+    draw_principal_connected_components_with_coloring(
+        labelled_image,
+        n,
+        background_color,
+        ComponentColoring::ByRank,
+    )
+}
+
+/// Draws the n largest connected components, choosing colors by `coloring`.
+///
+/// See [`draw_principal_connected_components`] for the other arguments.
+pub fn draw_principal_connected_components_with_coloring<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    n: usize,
+    background_color: Rgba<u8>,
+    coloring: ComponentColoring,
+) -> RgbaImage {
+    draw_principal_connected_components_with_background(
+        labelled_image,
+        L::zero(),
+        n,
+        background_color,
+        coloring,
+    )
+}
 
-    let mut sorted_counts: Vec<_> = counts.into_iter().collect();
-    sorted_counts.sort_by(|a, b| b.1.cmp(&a.1));
+/// Draws the n largest connected components of an image whose background label is not 0.
+///
+/// See [`draw_principal_connected_components`] and
+/// [`draw_principal_connected_components_with_coloring`] for the other arguments.
+pub fn draw_principal_connected_components_with_background<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    n: usize,
+    background_color: Rgba<u8>,
+    coloring: ComponentColoring,
+) -> RgbaImage {
+    let labels: &[L] = labelled_image.as_raw();
+    let dense_len = dense_table_len(labels);
 
-    let principal_labels: Vec<u32> = sorted_counts
-        .iter()
-        .take(n)
-        .map(|(label, _)| *label)
-        .collect();
+    let mut sorted_counts = count_labels(labels, background_label, dense_len);
+    sorted_counts.sort_unstable_by_key(|&(label, count)| (Reverse(count), label));
 
-    let colors = generate_contrasting_colors(n, 255);
-    let color_map: HashMap<u32, Rgba<u8>> = principal_labels.into_iter().zip(colors).collect();
+    let principal_labels = sorted_counts.iter().take(n).map(|&(label, _)| label);
+    let colors: Vec<(L, Rgba<u8>)> = match coloring {
+        ComponentColoring::ByRank => principal_labels
+            .zip(generate_contrasting_colors(n, 255))
+            .collect(),
+        ComponentColoring::ByLabel => principal_labels
+            .map(|label| (label, color_for_id(label.into(), 255)))
+            .collect(),
+    };
 
     let (width, height) = labelled_image.dimensions();
     let mut output_image = RgbaImage::from_pixel(width, height, background_color);
+    match dense_len {
+        Some(len) => {
+            let mut table = vec![None; len];
+            for (label, color) in colors {
+                table[dense_index(label)] = Some(color);
+            }
+            paint_labels(&mut output_image, labels, |label| table[dense_index(label)]);
+        }
+        None => {
+            let color_map: HashMap<L, Rgba<u8>> = colors.into_iter().collect();
+            paint_labels(&mut output_image, labels, |label| {
+                color_map.get(&label).copied()
+            });
+        }
+    }
+
+    output_image
+}
+
+/// Labels below this value are counted and looked up in dense tables instead of hash maps.
+/// A table takes 8 bytes per label, so larger labels, e.g. hashed from ids, use hash maps.
+const DENSE_LABEL_LIMIT: u64 = 1 << 20;
+
+/// Images with fewer pixels than this are counted on one thread.
+#[cfg(feature = "rayon")]
+const PARALLEL_CHUNK_LEN: usize = 1 << 14;
 
+/// The length of a table indexed by every label in `labels`, if it is small enough.
+fn dense_table_len<L: Label>(labels: &[L]) -> Option<usize> {
+    let max_label: u64 = labels.iter().map(|&label| label.into()).max()?;
+    (max_label < DENSE_LABEL_LIMIT).then(|| max_label as usize + 1)
+}
+
+fn dense_index<L: Label>(label: L) -> usize {
+    let index: u64 = label.into();
+    index as usize
+}
+
+/// Applies `count` to one chunk of `labels` per thread and merges the partial results, so
+/// at most one partial table per thread is alive.
+#[cfg(feature = "rayon")]
+fn count_chunks<L: Label, C: Send>(
+    labels: &[L],
+    count: impl Fn(&[L]) -> C + Sync + Send,
+    merge: impl Fn(C, C) -> C + Sync + Send,
+) -> Option<C> {
+    let chunk_len = labels
+        .len()
+        .div_ceil(rayon::current_num_threads())
+        .max(PARALLEL_CHUNK_LEN);
+    labels.par_chunks(chunk_len).map(count).reduce_with(merge)
+}
+
+/// The pixel count of every label except the background, in no particular order.
+fn count_labels<L: Label>(
+    labels: &[L],
+    background_label: L,
+    dense_len: Option<usize>,
+) -> Vec<(L, u64)> {
+    let Some(len) = dense_len else {
+        let count = |chunk: &[L]| {
+            let mut counts: HashMap<L, u64> = HashMap::new();
+            for &label in chunk {
+                *counts.entry(label).or_insert(0) += 1;
+            }
+            counts
+        };
+        #[cfg(feature = "rayon")]
+        let mut counts = count_chunks(labels, count, |mut a, b| {
+            for (label, n) in b {
+                *a.entry(label).or_insert(0) += n;
+            }
+            a
+        })
+        .unwrap_or_default();
+        #[cfg(not(feature = "rayon"))]
+        let mut counts = count(labels);
+        counts.remove(&background_label);
+        return counts.into_iter().collect();
+    };
+
+    let count = |chunk: &[L]| {
+        let mut counts = vec![0u64; len];
+        for &label in chunk {
+            counts[dense_index(label)] += 1;
+        }
+        counts
+    };
+    #[cfg(feature = "rayon")]
+    let counts = count_chunks(labels, count, |mut a, b| {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+        a
+    })
+    .unwrap_or_default();
+    #[cfg(not(feature = "rayon"))]
+    let counts = count(labels);
+
+    counts
+        .into_iter()
+        .enumerate()
+        .filter(|&(index, count)| count > 0 && index != dense_index(background_label))
+        .map(|(index, count)| (L::from(index).unwrap(), count))
+        .collect()
+}
+
+/// Sets every pixel of `canvas` whose label has a color to that color.
+fn paint_labels<L: Label>(
+    canvas: &mut RgbaImage,
+    labels: &[L],
+    color_of: impl Fn(L) -> Option<Rgba<u8>> + Sync,
+) {
+    let paint = |(pixel, &label): (&mut [u8], &L)| {
+        if let Some(color) = color_of(label) {
+            pixel.copy_from_slice(&color.0);
+        }
+    };
+    #[cfg(feature = "rayon")]
+    canvas
+        .par_chunks_exact_mut(4)
+        .zip(labels.par_iter())
+        .for_each(paint);
+    #[cfg(not(feature = "rayon"))]
+    canvas.chunks_exact_mut(4).zip(labels).for_each(paint);
+}
+
+/// Which parts of a component [`overlay_components_mut`] paints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayStyle {
+    /// Only the boundary pixels, opaque.
+    Outline,
+    /// Every pixel, blended with the given opacity.
+    Fill,
+    /// A blended fill with an opaque boundary.
+    FillAndOutline,
+}
+
+/// Options for [`overlay_components_mut`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverlayOptions {
+    /// Which parts of the selected components to paint.
+    pub style: OverlayStyle,
+    /// The opacity of the fill, from 0 (invisible) to 1 (solid).
+    pub opacity: f32,
+    /// If set, pixels of components that are not selected are darkened to this fraction of
+    /// their brightness, e.g. 0.3. Background pixels are left alone.
+    pub dim_unselected: Option<f32>,
+    /// How colors are assigned. `ByRank` ranks the selected components by area.
+    pub coloring: ComponentColoring,
+}
+
+impl Default for OverlayOptions {
+    fn default() -> Self {
+        OverlayOptions {
+            style: OverlayStyle::FillAndOutline,
+            opacity: 0.4,
+            dim_unselected: None,
+            coloring: ComponentColoring::ByLabel,
+        }
+    }
+}
+
+/// Blends colored components onto the original image, keeping it visible underneath.
+///
+/// Boundary pixels are component pixels with a 4-neighbor of another label or on the image
+/// border. Only the color channels are changed; the alpha channel of an `RgbaImage` is
+/// kept.
+///
+/// # Arguments
+/// * `canvas` - The original image, an `RgbImage` or `RgbaImage` of the same size as `labelled_image`.
+/// * `labelled_image` - The labelled image generated by a function like `imageproc::region_labelling::connected_components`.
+/// * `background_label` - The label of background pixels, which are never painted or dimmed.
+/// * `selected` - The labels to highlight, or `None` for every component.
+/// * `options` - Style, opacity, dimming and coloring.
+///
+/// # Panics
+/// Panics if the images differ in size or `canvas` has fewer than three channels.
+pub fn overlay_components_mut<L, P>(
+    canvas: &mut ImageBuffer<P, Vec<u8>>,
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    selected: Option<&[L]>,
+    options: &OverlayOptions,
+) where
+    L: Label,
+    P: Pixel<Subpixel = u8>,
+{
+    assert_eq!(
+        canvas.dimensions(),
+        labelled_image.dimensions(),
+        "canvas and labelled image must have the same size"
+    );
+    assert!(P::CHANNEL_COUNT >= 3, "canvas must be an RGB or RGBA image");
+
+    let areas = label_areas(labelled_image, background_label);
+    let mut selected: Vec<(L, u64)> = match selected {
+        Some(labels) => labels
+            .iter()
+            .filter_map(|label| areas.get(label).map(|&area| (*label, area)))
+            .collect(),
+        None => areas.into_iter().collect(),
+    };
+    selected.sort_unstable_by_key(|&(label, area)| (std::cmp::Reverse(area), label));
+    selected.dedup();
+
+    let color_map: HashMap<L, Rgba<u8>> = match options.coloring {
+        ComponentColoring::ByRank => selected
+            .iter()
+            .map(|&(label, _)| label)
+            .zip(generate_contrasting_colors(selected.len(), 255))
+            .collect(),
+        ComponentColoring::ByLabel => selected
+            .iter()
+            .map(|&(label, _)| (label, color_for_id(label.into(), 255)))
+            .collect(),
+    };
+
+    let (width, height) = labelled_image.dimensions();
+    let opacity = options.opacity.clamp(0.0, 1.0);
     for (x, y, pixel) in labelled_image.enumerate_pixels() {
         let label = pixel[0];
-        if let Some(color) = color_map.get(&label) {
-            output_image.put_pixel(x, y, *color);
+        if label == background_label {
+            continue;
+        }
+        let channels = &mut canvas.get_pixel_mut(x, y).channels_mut()[..3];
+
+        let Some(color) = color_map.get(&label) else {
+            if let Some(factor) = options.dim_unselected {
+                for channel in channels {
+                    *channel = (*channel as f32 * factor.clamp(0.0, 1.0)).round() as u8;
+                }
+            }
+            continue;
+        };
+
+        let on_boundary = x == 0
+            || y == 0
+            || x + 1 == width
+            || y + 1 == height
+            || [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .into_iter()
+                .any(|(nx, ny)| labelled_image.get_pixel(nx, ny)[0] != label);
+        let alpha = match options.style {
+            OverlayStyle::Outline | OverlayStyle::FillAndOutline if on_boundary => 1.0,
+            OverlayStyle::Outline => continue,
+            OverlayStyle::Fill | OverlayStyle::FillAndOutline => opacity,
+        };
+        for (channel, &value) in channels.iter_mut().zip(&color.0[..3]) {
+            *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
         }
     }
+}
 
-    output_image
+/// A labelled image whose surviving components were renumbered to `1..=K`.
+///
+/// If the background label is not 0, it is skipped when numbering, so new labels never
+/// collide with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelabelledImage<L: Label = u32> {
+    /// The new label image. Removed components become background.
+    pub image: ImageBuffer<Luma<L>, Vec<L>>,
+    /// Maps every surviving old label to its new label. Removed labels are absent.
+    pub mapping: BTreeMap<L, L>,
+}
+
+/// Keeps the components whose pixel area lies in `area_range` and relabels them to
+/// `1..=K` (MATLAB's `bwareaopen`, generalized to an upper bound).
+///
+/// New labels follow the order of the old ones, so relabelling is stable.
+///
+/// # Examples
+///
+/// ```
+/// use image::{ImageBuffer, Luma};
+/// use image_debug_utils::region_labelling::filter_components_by_area;
+///
+/// let labels = ImageBuffer::<Luma<u16>, _>::from_raw(4, 1, vec![5, 5, 0, 9]).unwrap();
+/// let filtered = filter_components_by_area(&labels, 0, 2..);
+///
+/// assert_eq!(filtered.image.into_raw(), vec![1, 1, 0, 0]);
+/// assert_eq!(filtered.mapping.get(&5), Some(&1));
+/// assert_eq!(filtered.mapping.get(&9), None);
+/// ```
+pub fn filter_components_by_area<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    area_range: impl RangeBounds<u64>,
+) -> RelabelledImage<L> {
+    let areas = label_areas(labelled_image, background_label);
+    relabel(
+        labelled_image,
+        background_label,
+        areas
+            .into_iter()
+            .filter(|(_, area)| area_range.contains(area))
+            .map(|(label, _)| label),
+    )
+}
+
+/// Keeps the `n` largest components and relabels them to `1..=K`, with `K <= n`.
+///
+/// Components of equal size are ranked by ascending label, as in
+/// [`draw_principal_connected_components`]. New labels follow the order of the old ones.
+pub fn keep_largest_components<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    n: usize,
+) -> RelabelledImage<L> {
+    let mut areas: Vec<(L, u64)> = label_areas(labelled_image, background_label)
+        .into_iter()
+        .collect();
+    areas.sort_unstable_by_key(|&(label, area)| (std::cmp::Reverse(area), label));
+    relabel(
+        labelled_image,
+        background_label,
+        areas.into_iter().take(n).map(|(label, _)| label),
+    )
+}
+
+/// Relabels all components to the compact range `1..=K`, keeping their order.
+pub fn relabel_sequential<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+) -> RelabelledImage<L> {
+    relabel(
+        labelled_image,
+        background_label,
+        label_areas(labelled_image, background_label).into_keys(),
+    )
+}
+
+/// The pixel count of every non-background label.
+fn label_areas<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+) -> BTreeMap<L, u64> {
+    let labels: &[L] = labelled_image.as_raw();
+    count_labels(labels, background_label, dense_table_len(labels))
+        .into_iter()
+        .collect()
+}
+
+/// Numbers the `kept` labels upwards from 1 in ascending order, skipping the background
+/// label, and paints the new image.
+fn relabel<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    kept: impl IntoIterator<Item = L>,
+) -> RelabelledImage<L> {
+    let mut kept: Vec<L> = kept.into_iter().collect();
+    kept.sort_unstable();
+
+    // There are at most as many kept labels as values other than the background, so
+    // wrapping around to 0 after the maximum always leaves enough new labels.
+    let mut next = L::zero();
+    let mut mapping = BTreeMap::new();
+    for label in kept {
+        loop {
+            next = if next == L::max_value() {
+                L::zero()
+            } else {
+                next + L::one()
+            };
+            if next != background_label {
+                break;
+            }
+        }
+        mapping.insert(label, next);
+    }
+
+    let (width, height) = labelled_image.dimensions();
+    let image = ImageBuffer::from_fn(width, height, |x, y| {
+        let label = labelled_image.get_pixel(x, y)[0];
+        Luma([mapping.get(&label).copied().unwrap_or(background_label)])
+    });
+
+    RelabelledImage { image, mapping }
+}
+
+/// An edge of a [`RegionAdjacencyGraph`] between two touching components.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionEdge<L = u32> {
+    /// The labels of the two components, the smaller one first.
+    pub labels: (L, L),
+    /// The number of pixel edges along which the two components touch.
+    pub boundary_length: u64,
+    /// The mean intensity of the pixels on both sides of the shared boundary, if an
+    /// intensity image was given. A weak boundary between two regions of one object
+    /// usually has an intensity close to that of the regions.
+    pub mean_boundary_intensity: Option<f64>,
+}
+
+/// The components of a labelled image and which of them touch, for example to decide
+/// which regions of an over-segmentation to merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionAdjacencyGraph<L = u32> {
+    /// One node per component, sorted by label.
+    pub nodes: Vec<ComponentStats<L>>,
+    /// One edge per pair of 4-adjacent components, sorted by labels.
+    pub edges: Vec<RegionEdge<L>>,
+}
+
+impl<L: Label> RegionAdjacencyGraph<L> {
+    /// Builds the graph in one pass over the image.
+    ///
+    /// Components are adjacent if a pixel of one is the left, right, top or bottom neighbor
+    /// of a pixel of the other. Background pixels separate components.
+    ///
+    /// # Arguments
+    /// * `labelled_image` - The labelled image generated by a function like `imageproc::region_labelling::connected_components`.
+    /// * `background_label` - The label of background pixels, which are not a node.
+    /// * `intensity` - An optional grayscale image of the same size, for [`RegionEdge::mean_boundary_intensity`].
+    ///
+    /// # Panics
+    /// Panics if `intensity` has a different size than `labelled_image`.
+    pub fn new(
+        labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+        background_label: L,
+        intensity: Option<&GrayImage>,
+    ) -> Self {
+        if let Some(intensity) = intensity {
+            assert_eq!(
+                intensity.dimensions(),
+                labelled_image.dimensions(),
+                "intensity and labelled image must have the same size"
+            );
+        }
+
+        let (width, height) = labelled_image.dimensions();
+        // (boundary length, intensity sum) per pair of labels.
+        let mut boundaries: BTreeMap<(L, L), (u64, f64)> = BTreeMap::new();
+        for (x, y, pixel) in labelled_image.enumerate_pixels() {
+            let label = pixel[0];
+            if label == background_label {
+                continue;
+            }
+            let right = (x + 1 < width).then(|| (x + 1, y));
+            let below = (y + 1 < height).then(|| (x, y + 1));
+            for (nx, ny) in [right, below].into_iter().flatten() {
+                let other = labelled_image.get_pixel(nx, ny)[0];
+                if other == background_label || other == label {
+                    continue;
+                }
+                let boundary = boundaries
+                    .entry((label.min(other), label.max(other)))
+                    .or_insert((0, 0.0));
+                boundary.0 += 1;
+                if let Some(intensity) = intensity {
+                    boundary.1 += (intensity.get_pixel(x, y)[0] as f64
+                        + intensity.get_pixel(nx, ny)[0] as f64)
+                        / 2.0;
+                }
+            }
+        }
+
+        RegionAdjacencyGraph {
+            nodes: component_stats(labelled_image, background_label),
+            edges: boundaries
+                .into_iter()
+                .map(|(labels, (length, sum))| RegionEdge {
+                    labels,
+                    boundary_length: length,
+                    mean_boundary_intensity: intensity.map(|_| sum / length as f64),
+                })
+                .collect(),
+        }
+    }
+
+    /// The labels of the components touching `label`, in ascending order.
+    pub fn neighbors(&self, label: L) -> Vec<L> {
+        let mut neighbors: Vec<L> = self
+            .edges
+            .iter()
+            .filter_map(|edge| match edge.labels {
+                (a, b) if a == label => Some(b),
+                (a, b) if b == label => Some(a),
+                _ => None,
+            })
+            .collect();
+        neighbors.sort_unstable();
+        neighbors
+    }
+
+    /// Exports the graph in the Graphviz DOT language, with node positions at the
+    /// centroids so that `neato -n` reproduces the image layout.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph rag {\n");
+        for node in &self.nodes {
+            let label: u64 = node.label.into();
+            dot.push_str(&format!(
+                "  {label} [label=\"{label}\\narea={}\", pos=\"{:.1},{:.1}\"];\n",
+                node.area, node.centroid.0, -node.centroid.1
+            ));
+        }
+        for edge in &self.edges {
+            let (a, b): (u64, u64) = (edge.labels.0.into(), edge.labels.1.into());
+            let mut attributes = format!("length={}", edge.boundary_length);
+            if let Some(mean) = edge.mean_boundary_intensity {
+                attributes.push_str(&format!(", intensity={mean:.2}"));
+            }
+            dot.push_str(&format!("  {a} -- {b} [label=\"{attributes}\"];\n"));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the graph as JSON, with `nodes` (label, area, bounding box as
+    /// `[x, y, width, height]`, centroid as `[x, y]`) and `edges` (labels, boundary length,
+    /// mean boundary intensity or `null`).
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<serde_json::Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let b = node.bounding_box;
+                serde_json::json!({
+                    "label": node.label.into(),
+                    "area": node.area,
+                    "bounding_box": [b.x, b.y, b.width, b.height],
+                    "centroid": [node.centroid.0, node.centroid.1],
+                })
+            })
+            .collect();
+        let edges: Vec<serde_json::Value> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                serde_json::json!({
+                    "labels": [edge.labels.0.into(), edge.labels.1.into()],
+                    "boundary_length": edge.boundary_length,
+                    "mean_boundary_intensity": edge.mean_boundary_intensity,
+                })
+            })
+            .collect();
+        serde_json::json!({ "nodes": nodes, "edges": edges }).to_string()
+    }
+}
+
+/// Draws a [`RegionAdjacencyGraph`] over a label visualization such as the output of
+/// [`draw_principal_connected_components`]: a cross at every centroid and a line between
+/// the centroids of adjacent components.
+pub fn draw_region_adjacency_graph_mut<L: Label>(
+    canvas: &mut RgbaImage,
+    graph: &RegionAdjacencyGraph<L>,
+    color: Rgba<u8>,
+) {
+    let centroids: HashMap<L, (f32, f32)> = graph
+        .nodes
+        .iter()
+        .map(|node| (node.label, (node.centroid.0 as f32, node.centroid.1 as f32)))
+        .collect();
+
+    for edge in &graph.edges {
+        draw_line_segment_mut(
+            canvas,
+            centroids[&edge.labels.0],
+            centroids[&edge.labels.1],
+            color,
+        );
+    }
+    for &(x, y) in centroids.values() {
+        draw_cross_mut(canvas, color, x.round() as i32, y.round() as i32);
+    }
+}
+
+/// How an object fared in [`compare_segmentations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectStatus {
+    /// Matched one-to-one with an object of the other segmentation.
+    Matched,
+    /// A reference object covered by several predicted objects, or one of those objects.
+    Split,
+    /// A predicted object covering several reference objects, or one of those objects.
+    Merged,
+    /// A reference object without a match.
+    Missed,
+    /// A predicted object without a match.
+    False,
+}
+
+/// The comparison of one object with the other segmentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectScore<L = u32> {
+    /// The label of the object.
+    pub label: L,
+    /// The matched object of the other segmentation, or the one with the highest IoU if
+    /// there is no match. `None` if the object only overlaps background.
+    pub counterpart: Option<L>,
+    /// The IoU with `counterpart`, 0 without one.
+    pub iou: f64,
+    /// The Dice coefficient (F1 of the pixels) with `counterpart`, 0 without one.
+    pub dice: f64,
+    /// Whether the object was matched, split, merged, missed or is false.
+    pub status: ObjectStatus,
+}
+
+/// Variation of information between two segmentations, in nats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariationOfInformation {
+    /// The sum of both conditional entropies; 0 for identical segmentations.
+    pub total: f64,
+    /// `H(prediction | reference)`: high when the prediction splits reference objects.
+    pub over_segmentation: f64,
+    /// `H(reference | prediction)`: high when the prediction merges reference objects.
+    pub under_segmentation: f64,
+}
+
+/// Per-object and global metrics between a reference and a predicted label image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentationComparison<L = u32> {
+    /// One entry per reference object, sorted by label.
+    pub reference: Vec<ObjectScore<L>>,
+    /// One entry per predicted object, sorted by label.
+    pub prediction: Vec<ObjectScore<L>>,
+    /// The Adjusted Rand Index over all pixels, background included: 1 for identical
+    /// segmentations, around 0 for unrelated ones.
+    pub adjusted_rand_index: f64,
+    /// The variation of information over all pixels, background included.
+    pub variation_of_information: VariationOfInformation,
+}
+
+/// Compares a predicted segmentation with a reference segmentation.
+///
+/// Objects are matched one-to-one greedily by descending IoU, among pairs with an IoU of at
+/// least `iou_threshold`; with a threshold above 0.5 the matching is unique. A predicted
+/// object is a significant part of a reference object if it covers at least `min_overlap`
+/// of the reference object's area. Reference objects with several significant parts are
+/// split; predicted objects that are a significant part of several reference objects are
+/// merges. Split and merge take precedence over the match status.
+///
+/// # Arguments
+/// * `reference` - The reference (ground truth) label image.
+/// * `prediction` - The label image to evaluate, of the same size.
+/// * `background_label` - The label of background pixels in both images.
+/// * `iou_threshold` - The minimum IoU of a match, typically 0.5.
+/// * `min_overlap` - The fraction of a reference object a predicted object must cover to count for splits and merges, e.g. 0.1.
+///
+/// # Panics
+/// Panics if the images differ in size.
+pub fn compare_segmentations<L: Label>(
+    reference: &ImageBuffer<Luma<L>, Vec<L>>,
+    prediction: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    iou_threshold: f64,
+    min_overlap: f64,
+) -> SegmentationComparison<L> {
+    assert_eq!(
+        reference.dimensions(),
+        prediction.dimensions(),
+        "reference and prediction must have the same size"
+    );
+
+    // The contingency table over all pixels, background included.
+    let mut table: HashMap<(L, L), u64> = HashMap::new();
+    for (r, p) in reference.pixels().zip(prediction.pixels()) {
+        *table.entry((r[0], p[0])).or_insert(0) += 1;
+    }
+    let mut reference_areas: BTreeMap<L, u64> = BTreeMap::new();
+    let mut prediction_areas: BTreeMap<L, u64> = BTreeMap::new();
+    for (&(r, p), &count) in &table {
+        *reference_areas.entry(r).or_insert(0) += count;
+        *prediction_areas.entry(p).or_insert(0) += count;
+    }
+
+    let adjusted_rand_index = adjusted_rand_index(&table, &reference_areas, &prediction_areas);
+    let variation_of_information =
+        variation_of_information(&table, &reference_areas, &prediction_areas);
+
+    // Object overlaps, without background.
+    let mut overlaps: Vec<(L, L, u64, f64)> = table
+        .iter()
+        .filter(|&(&(r, p), _)| r != background_label && p != background_label)
+        .map(|(&(r, p), &intersection)| {
+            let union = reference_areas[&r] + prediction_areas[&p] - intersection;
+            (r, p, intersection, intersection as f64 / union as f64)
+        })
+        .collect();
+    overlaps.sort_unstable_by(|a, b| b.3.total_cmp(&a.3).then((a.0, a.1).cmp(&(b.0, b.1))));
+
+    let mut reference_matches: HashMap<L, L> = HashMap::new();
+    let mut prediction_matches: HashMap<L, L> = HashMap::new();
+    for &(r, p, _, iou) in &overlaps {
+        if iou >= iou_threshold
+            && !reference_matches.contains_key(&r)
+            && !prediction_matches.contains_key(&p)
+        {
+            reference_matches.insert(r, p);
+            prediction_matches.insert(p, r);
+        }
+    }
+
+    let mut parts: HashMap<L, usize> = HashMap::new();
+    let mut covered: HashMap<L, usize> = HashMap::new();
+    let significant: Vec<(L, L)> = overlaps
+        .iter()
+        .filter(|&&(r, _, intersection, _)| {
+            intersection as f64 >= min_overlap * reference_areas[&r] as f64
+        })
+        .map(|&(r, p, _, _)| (r, p))
+        .collect();
+    for &(r, p) in &significant {
+        *parts.entry(r).or_insert(0) += 1;
+        *covered.entry(p).or_insert(0) += 1;
+    }
+    let split_references: HashSet<L> = parts
+        .iter()
+        .filter(|&(_, &n)| n > 1)
+        .map(|(&r, _)| r)
+        .collect();
+    let merged_predictions: HashSet<L> = covered
+        .iter()
+        .filter(|&(_, &n)| n > 1)
+        .map(|(&p, _)| p)
+        .collect();
+
+    let mut reference_status: HashMap<L, ObjectStatus> = HashMap::new();
+    let mut prediction_status: HashMap<L, ObjectStatus> = HashMap::new();
+    for &(r, p) in &significant {
+        if split_references.contains(&r) {
+            reference_status.insert(r, ObjectStatus::Split);
+            prediction_status.entry(p).or_insert(ObjectStatus::Split);
+        }
+    }
+    for &(r, p) in &significant {
+        if merged_predictions.contains(&p) {
+            prediction_status.insert(p, ObjectStatus::Merged);
+            reference_status.entry(r).or_insert(ObjectStatus::Merged);
+        }
+    }
+
+    // Overlaps are sorted by descending IoU, so the first one of each object is its best.
+    let mut best_for_reference: HashMap<L, L> = HashMap::new();
+    let mut best_for_prediction: HashMap<L, L> = HashMap::new();
+    for &(r, p, _, _) in &overlaps {
+        best_for_reference.entry(r).or_insert(p);
+        best_for_prediction.entry(p).or_insert(r);
+    }
+
+    let score = |label: L,
+                 area: u64,
+                 matches: &HashMap<L, L>,
+                 statuses: &HashMap<L, ObjectStatus>,
+                 unmatched: ObjectStatus,
+                 is_reference: bool| {
+        let best = if is_reference {
+            best_for_reference.get(&label)
+        } else {
+            best_for_prediction.get(&label)
+        };
+        let counterpart = matches.get(&label).or(best).copied();
+        let (iou, dice) = match counterpart {
+            Some(other) => {
+                let key = if is_reference {
+                    (label, other)
+                } else {
+                    (other, label)
+                };
+                let intersection = table[&key] as f64;
+                let other_area = if is_reference {
+                    prediction_areas[&other]
+                } else {
+                    reference_areas[&other]
+                } as f64;
+                let union = area as f64 + other_area - intersection;
+                (
+                    intersection / union,
+                    2.0 * intersection / (area as f64 + other_area),
+                )
+            }
+            None => (0.0, 0.0),
+        };
+        let status = statuses
+            .get(&label)
+            .copied()
+            .unwrap_or(if matches.contains_key(&label) {
+                ObjectStatus::Matched
+            } else {
+                unmatched
+            });
+        ObjectScore {
+            label,
+            counterpart,
+            iou,
+            dice,
+            status,
+        }
+    };
+
+    SegmentationComparison {
+        reference: reference_areas
+            .iter()
+            .filter(|&(&label, _)| label != background_label)
+            .map(|(&label, &area)| {
+                score(
+                    label,
+                    area,
+                    &reference_matches,
+                    &reference_status,
+                    ObjectStatus::Missed,
+                    true,
+                )
+            })
+            .collect(),
+        prediction: prediction_areas
+            .iter()
+            .filter(|&(&label, _)| label != background_label)
+            .map(|(&label, &area)| {
+                score(
+                    label,
+                    area,
+                    &prediction_matches,
+                    &prediction_status,
+                    ObjectStatus::False,
+                    false,
+                )
+            })
+            .collect(),
+        adjusted_rand_index,
+        variation_of_information,
+    }
+}
+
+/// Renders the object statuses of a [`compare_segmentations`] result.
+///
+/// Matched objects are gray, split ones yellow, merged ones magenta, missed reference
+/// objects blue and false predicted objects red. Where objects overlap, split and merged
+/// win over missed, missed over false, and false over matched.
+///
+/// # Arguments
+/// * `reference` - The reference label image passed to [`compare_segmentations`].
+/// * `prediction` - The predicted label image passed to [`compare_segmentations`].
+/// * `background_label` - The label of background pixels in both images.
+/// * `comparison` - The result of [`compare_segmentations`].
+/// * `background_color` - The color of pixels that are background in both images.
+pub fn draw_segmentation_comparison<L: Label>(
+    reference: &ImageBuffer<Luma<L>, Vec<L>>,
+    prediction: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    comparison: &SegmentationComparison<L>,
+    background_color: Rgba<u8>,
+) -> RgbaImage {
+    let color = |status: ObjectStatus| match status {
+        ObjectStatus::Matched => Rgba([128, 128, 128, 255]),
+        ObjectStatus::Split => Rgba([255, 200, 0, 255]),
+        ObjectStatus::Merged => Rgba([200, 0, 255, 255]),
+        ObjectStatus::Missed => Rgba([0, 0, 255, 255]),
+        ObjectStatus::False => Rgba([255, 0, 0, 255]),
+    };
+    let priority = |status: ObjectStatus| match status {
+        ObjectStatus::Split | ObjectStatus::Merged => 3,
+        ObjectStatus::Missed => 2,
+        ObjectStatus::False => 1,
+        ObjectStatus::Matched => 0,
+    };
+
+    let reference_status: HashMap<L, ObjectStatus> = comparison
+        .reference
+        .iter()
+        .map(|o| (o.label, o.status))
+        .collect();
+    let prediction_status: HashMap<L, ObjectStatus> = comparison
+        .prediction
+        .iter()
+        .map(|o| (o.label, o.status))
+        .collect();
+
+    let (width, height) = reference.dimensions();
+    RgbaImage::from_fn(width, height, |x, y| {
+        let r = reference.get_pixel(x, y)[0];
+        let p = prediction.get_pixel(x, y)[0];
+        let statuses = [
+            (r != background_label)
+                .then(|| reference_status.get(&r))
+                .flatten(),
+            (p != background_label)
+                .then(|| prediction_status.get(&p))
+                .flatten(),
+        ];
+        statuses
+            .into_iter()
+            .flatten()
+            .max_by_key(|&&status| priority(status))
+            .map_or(background_color, |&status| color(status))
+    })
+}
+
+fn pairs(n: u64) -> f64 {
+    n as f64 * (n as f64 - 1.0) / 2.0
+}
+
+fn adjusted_rand_index<L: Label>(
+    table: &HashMap<(L, L), u64>,
+    reference_areas: &BTreeMap<L, u64>,
+    prediction_areas: &BTreeMap<L, u64>,
+) -> f64 {
+    let total: u64 = reference_areas.values().sum();
+    if total < 2 {
+        // Without a pair of pixels, there is nothing the segmentations could disagree on.
+        return 1.0;
+    }
+    let index: f64 = table.values().map(|&n| pairs(n)).sum();
+    let reference_pairs: f64 = reference_areas.values().map(|&n| pairs(n)).sum();
+    let prediction_pairs: f64 = prediction_areas.values().map(|&n| pairs(n)).sum();
+    let expected = reference_pairs * prediction_pairs / pairs(total);
+    let maximum = (reference_pairs + prediction_pairs) / 2.0;
+    if maximum == expected {
+        // Both segmentations put every pixel in one segment, or every pixel in its own.
+        1.0
+    } else {
+        (index - expected) / (maximum - expected)
+    }
+}
+
+fn variation_of_information<L: Label>(
+    table: &HashMap<(L, L), u64>,
+    reference_areas: &BTreeMap<L, u64>,
+    prediction_areas: &BTreeMap<L, u64>,
+) -> VariationOfInformation {
+    let total = reference_areas.values().sum::<u64>() as f64;
+    let entropy = |areas: &BTreeMap<L, u64>| -> f64 {
+        areas
+            .values()
+            .map(|&n| {
+                let p = n as f64 / total;
+                -p * p.ln()
+            })
+            .sum()
+    };
+    let joint: f64 = table
+        .values()
+        .map(|&n| {
+            let p = n as f64 / total;
+            -p * p.ln()
+        })
+        .sum();
+
+    let over_segmentation = (joint - entropy(reference_areas)).max(0.0);
+    let under_segmentation = (joint - entropy(prediction_areas)).max(0.0);
+    VariationOfInformation {
+        total: over_segmentation + under_segmentation,
+        over_segmentation,
+        under_segmentation,
+    }
+}
+
+/// Finds the contours of every label: its outer borders and the borders of its holes.
+///
+/// Each label is traced with `imageproc::contours::find_contours` inside its own bounding
+/// box, found in a single pass over the image beforehand. The cost is proportional to the
+/// total bounding box area instead of labels × pixels. The result matches binarizing the
+/// whole image once per label, except at the image edge: `find_contours` reports objects
+/// touching the left or top edge as holes without parent, while here every label is
+/// traced with a frame of background around it. Pixels of other labels count as
+/// background for a label, so a component enclosed by another one produces a hole in it.
+///
+/// # Arguments
+/// * `labelled_image` - The labelled image generated by a function like `imageproc::region_labelling::connected_components`.
+/// * `background_label` - The label of background pixels, which get no contours.
+///
+/// # Returns
+/// The contours of each label, keyed by label. `parent` indices refer to the contours of
+/// the same label, so every list can be used with the functions of the `contours` module.
+pub fn label_contours<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+) -> BTreeMap<L, Vec<Contour<i32>>> {
+    component_stats(labelled_image, background_label)
+        .into_iter()
+        .map(|component| {
+            let b = component.bounding_box;
+            // A one pixel frame of background, so that components touching the image edge
+            // still get an outer border.
+            let mask = GrayImage::from_fn(b.width + 2, b.height + 2, |x, y| {
+                let inside = (1..=b.width).contains(&x) && (1..=b.height).contains(&y);
+                Luma([u8::from(
+                    inside
+                        && labelled_image.get_pixel(b.x + x - 1, b.y + y - 1)[0] == component.label,
+                )])
+            });
+            let mut contours: Vec<Contour<i32>> = find_contours(&mask);
+            for contour in &mut contours {
+                for point in &mut contour.points {
+                    point.x += b.x as i32 - 1;
+                    point.y += b.y as i32 - 1;
+                }
+            }
+            (component.label, contours)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -116,4 +1350,504 @@ mod tests {
 
         assert_eq!(result_image, expected_image);
     }
+
+    #[test]
+    fn test_component_stats() {
+        // Label 1 is a ring with a one pixel hole, label 2 a 2x1 bar, label 3 a single
+        // pixel in the ring's hole.
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            1, 1, 1, 0, 0,
+            1, 3, 1, 0, 2,
+            1, 1, 1, 0, 2,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(5, 3, labels_vec).unwrap();
+
+        let stats = component_stats(&labelled_image, 0);
+        assert_eq!(
+            stats.iter().map(|s| s.label).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        let ring = &stats[0];
+        assert_eq!(ring.area, 8);
+        assert_eq!(
+            ring.bounding_box,
+            Rect {
+                x: 0,
+                y: 0,
+                width: 3,
+                height: 3
+            }
+        );
+        assert_eq!(ring.centroid, (1.0, 1.0));
+        assert_eq!(ring.first_pixel, (0, 0));
+        assert_eq!(ring.shape, None);
+
+        let bar = &stats[1];
+        assert_eq!(bar.area, 2);
+        assert_eq!(bar.centroid, (4.0, 1.5));
+        assert_eq!(bar.first_pixel, (4, 1));
+
+        let with_shape = component_stats_with_shape(&labelled_image, 0);
+        let shapes: Vec<ShapeStats> = with_shape.iter().map(|s| s.shape.unwrap()).collect();
+        assert_eq!(
+            shapes,
+            vec![
+                ShapeStats {
+                    perimeter: 16,
+                    holes: 1
+                },
+                ShapeStats {
+                    perimeter: 6,
+                    holes: 0
+                },
+                ShapeStats {
+                    perimeter: 4,
+                    holes: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_principal_components_tie_breaking_and_label_colors() {
+        // Four components of two pixels each.
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            4, 4, 0, 2, 2,
+            0, 0, 0, 0, 0,
+            9, 9, 0, 7, 7,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(5, 3, labels_vec).unwrap();
+        let background = Rgba([0, 0, 0, 255]);
+
+        let colors = generate_contrasting_colors(2, 255);
+        for _ in 0..10 {
+            let result = draw_principal_connected_components(&labelled_image, 2, background);
+            assert_eq!(*result.get_pixel(3, 0), colors[0]);
+            assert_eq!(*result.get_pixel(0, 0), colors[1]);
+            assert_eq!(*result.get_pixel(3, 2), background);
+        }
+
+        // Label colors do not depend on `n`.
+        for n in [1, 4] {
+            let result = draw_principal_connected_components_with_coloring(
+                &labelled_image,
+                n,
+                background,
+                ComponentColoring::ByLabel,
+            );
+            assert_eq!(*result.get_pixel(3, 0), color_for_id(2, 255));
+        }
+    }
+
+    /// The original hash map implementation, which the dense and parallel paths must match.
+    fn draw_principal_reference<L: Label>(
+        labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+        background_label: L,
+        n: usize,
+        background_color: Rgba<u8>,
+        coloring: ComponentColoring,
+    ) -> RgbaImage {
+        let mut counts = HashMap::new();
+        for label in labelled_image.pixels() {
+            if label[0] != background_label {
+                *counts.entry(label[0]).or_insert(0) += 1;
+            }
+        }
+        let mut sorted_counts: Vec<_> = counts.into_iter().collect();
+        sorted_counts.sort_unstable_by_key(|&(label, count)| (Reverse(count), label));
+        let principal_labels = sorted_counts.iter().take(n).map(|&(label, _)| label);
+        let color_map: HashMap<L, Rgba<u8>> = match coloring {
+            ComponentColoring::ByRank => principal_labels
+                .zip(generate_contrasting_colors(n, 255))
+                .collect(),
+            ComponentColoring::ByLabel => principal_labels
+                .map(|label| (label, color_for_id(label.into(), 255)))
+                .collect(),
+        };
+        let (width, height) = labelled_image.dimensions();
+        let mut output_image = RgbaImage::from_pixel(width, height, background_color);
+        for (x, y, pixel) in labelled_image.enumerate_pixels() {
+            if let Some(color) = color_map.get(&pixel[0]) {
+                output_image.put_pixel(x, y, *color);
+            }
+        }
+        output_image
+    }
+
+    #[test]
+    fn test_principal_components_match_reference() {
+        // A simple LCG, so the test needs no random number crate.
+        let mut state = 12345u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 33
+        };
+        // Large enough to span several parallel chunks.
+        let (width, height) = (300, 200);
+        let small: Vec<u32> = (0..width * height).map(|_| (next() % 500) as u32).collect();
+        let background = Rgba([1, 2, 3, 255]);
+
+        let check = |image: &ImageBuffer<Luma<u64>, Vec<u64>>, background_label: u64| {
+            for n in [0, 5, 1000] {
+                for coloring in [ComponentColoring::ByRank, ComponentColoring::ByLabel] {
+                    assert_eq!(
+                        draw_principal_connected_components_with_background(
+                            image,
+                            background_label,
+                            n,
+                            background,
+                            coloring,
+                        ),
+                        draw_principal_reference(image, background_label, n, background, coloring),
+                        "n = {n}, {coloring:?}",
+                    );
+                }
+            }
+        };
+
+        // Small labels take the dense path, labels hashed from ids the hash map path.
+        let dense = ImageBuffer::from_fn(width, height, |x, y| {
+            Luma([small[(y * width + x) as usize] as u64])
+        });
+        assert!(dense_table_len(dense.as_raw()).is_some());
+        check(&dense, 0);
+        let sparse = ImageBuffer::from_fn(width, height, |x, y| {
+            Luma([(small[(y * width + x) as usize] as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)])
+        });
+        assert!(dense_table_len(sparse.as_raw()).is_none());
+        check(&sparse, sparse.get_pixel(0, 0)[0]);
+
+        let bytes = ImageBuffer::from_fn(width, height, |x, y| {
+            Luma([small[(y * width + x) as usize] as u8])
+        });
+        assert_eq!(
+            draw_principal_connected_components_with_background(
+                &bytes,
+                255,
+                7,
+                background,
+                ComponentColoring::ByRank,
+            ),
+            draw_principal_reference(&bytes, 255, 7, background, ComponentColoring::ByRank),
+        );
+    }
+
+    #[test]
+    fn test_filter_and_relabel_components() {
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            3, 3, 3, 0, 8,
+            3, 0, 0, 0, 8,
+            0, 0, 5, 5, 0,
+            6, 0, 0, 0, 0,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(5, 4, labels_vec).unwrap();
+
+        let small_removed = filter_components_by_area(&labelled_image, 0, 2..);
+        assert_eq!(
+            small_removed.mapping,
+            BTreeMap::from([(3, 1), (5, 2), (8, 3)])
+        );
+        #[rustfmt::skip]
+        assert_eq!(small_removed.image.into_raw(), vec![
+            1, 1, 1, 0, 3,
+            1, 0, 0, 0, 3,
+            0, 0, 2, 2, 0,
+            0, 0, 0, 0, 0,
+        ]);
+
+        let mid_sized = filter_components_by_area(&labelled_image, 0, 2..=3);
+        assert_eq!(mid_sized.mapping, BTreeMap::from([(5, 1), (8, 2)]));
+
+        // Labels 5 and 8 tie for second place; the lower label wins.
+        let largest = keep_largest_components(&labelled_image, 0, 2);
+        assert_eq!(largest.mapping, BTreeMap::from([(3, 1), (5, 2)]));
+        assert_eq!(
+            keep_largest_components(&labelled_image, 0, 10)
+                .mapping
+                .len(),
+            4
+        );
+
+        let sequential = relabel_sequential(&labelled_image, 0);
+        assert_eq!(
+            sequential.mapping,
+            BTreeMap::from([(3, 1), (5, 2), (6, 3), (8, 4)])
+        );
+        assert_eq!(sequential.image.get_pixel(0, 3)[0], 3);
+    }
+
+    #[test]
+    fn test_other_label_types_and_background() {
+        // An 8-bit mask where 255 is the background.
+        #[rustfmt::skip]
+        let labels_vec: Vec<u8> = vec![
+            255, 0, 0, 255,
+            255, 0, 255, 7,
+            3, 255, 255, 7,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u8>, _>::from_raw(4, 3, labels_vec).unwrap();
+
+        let stats = component_stats(&labelled_image, 255);
+        assert_eq!(
+            stats.iter().map(|s| (s.label, s.area)).collect::<Vec<_>>(),
+            vec![(0, 3), (3, 1), (7, 2)]
+        );
+
+        let relabelled = relabel_sequential(&labelled_image, 255);
+        assert_eq!(relabelled.mapping, BTreeMap::from([(0, 1), (3, 2), (7, 3)]));
+        assert_eq!(relabelled.image.get_pixel(0, 0)[0], 255);
+
+        // Skips the background label when numbering.
+        let with_background_two =
+            ImageBuffer::<Luma<u16>, _>::from_raw(3, 1, vec![9, 2, 4]).unwrap();
+        let relabelled = relabel_sequential(&with_background_two, 2);
+        assert_eq!(relabelled.mapping, BTreeMap::from([(4, 1), (9, 3)]));
+
+        let background = Rgba([0, 0, 0, 255]);
+        let drawn = draw_principal_connected_components_with_background(
+            &labelled_image,
+            255,
+            1,
+            background,
+            ComponentColoring::ByRank,
+        );
+        assert_eq!(
+            *drawn.get_pixel(1, 0),
+            generate_contrasting_colors(1, 255)[0]
+        );
+        assert_eq!(*drawn.get_pixel(3, 1), background);
+
+        let wide = ImageBuffer::<Luma<u64>, _>::from_raw(2, 1, vec![0, u64::MAX]).unwrap();
+        assert_eq!(component_stats(&wide, 0)[0].label, u64::MAX);
+    }
+
+    #[test]
+    fn test_overlay_components() {
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            1, 1, 1, 0, 2,
+            1, 1, 1, 0, 2,
+            1, 1, 1, 0, 0,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(5, 3, labels_vec).unwrap();
+        let gray = image::Rgb([100, 100, 100]);
+
+        let options = OverlayOptions {
+            style: OverlayStyle::FillAndOutline,
+            opacity: 0.5,
+            dim_unselected: Some(0.5),
+            coloring: ComponentColoring::ByRank,
+        };
+        let mut canvas = image::RgbImage::from_pixel(5, 3, gray);
+        overlay_components_mut(&mut canvas, &labelled_image, 0, Some(&[1]), &options);
+
+        // Opaque boundary, half-blended interior, dimmed unselected component.
+        assert_eq!(
+            generate_contrasting_colors(1, 255)[0],
+            Rgba([242, 13, 13, 255])
+        );
+        assert_eq!(canvas.get_pixel(0, 0).0, [242, 13, 13]);
+        assert_eq!(canvas.get_pixel(1, 1).0, [171, 57, 57]);
+        assert_eq!(canvas.get_pixel(4, 0).0, [50, 50, 50]);
+        assert_eq!(*canvas.get_pixel(3, 0), gray);
+
+        let mut canvas = RgbaImage::from_pixel(5, 3, Rgba([100, 100, 100, 7]));
+        let outline = OverlayOptions {
+            style: OverlayStyle::Outline,
+            ..OverlayOptions::default()
+        };
+        overlay_components_mut(&mut canvas, &labelled_image, 0, None, &outline);
+        assert_eq!(*canvas.get_pixel(1, 1), Rgba([100, 100, 100, 7]));
+        let boundary = color_for_id(2, 255);
+        assert_eq!(
+            *canvas.get_pixel(4, 1),
+            Rgba([boundary[0], boundary[1], boundary[2], 7])
+        );
+    }
+
+    #[test]
+    fn test_region_adjacency_graph() {
+        // Labels 1 and 2 share two pixel edges, as do 2 and 3; 4 only touches the
+        // background.
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            1, 2, 2, 0, 4,
+            1, 2, 3, 0, 0,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(5, 2, labels_vec).unwrap();
+        #[rustfmt::skip]
+        let intensity = GrayImage::from_raw(5, 2, vec![
+            10, 30, 0, 0, 0,
+            10, 50, 90, 0, 0,
+        ]).unwrap();
+
+        let graph = RegionAdjacencyGraph::new(&labelled_image, 0, Some(&intensity));
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(
+            graph.edges,
+            vec![
+                RegionEdge {
+                    labels: (1, 2),
+                    boundary_length: 2,
+                    mean_boundary_intensity: Some(25.0),
+                },
+                RegionEdge {
+                    labels: (2, 3),
+                    boundary_length: 2,
+                    mean_boundary_intensity: Some(57.5),
+                },
+            ]
+        );
+        assert_eq!(graph.neighbors(2), vec![1, 3]);
+        assert!(graph.neighbors(4).is_empty());
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph rag {"));
+        assert!(dot.contains("  1 -- 2 [label=\"length=2, intensity=25.00\"];"));
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+        assert_eq!(json["nodes"][3]["label"], 4);
+        assert_eq!(json["edges"][1]["labels"], serde_json::json!([2, 3]));
+
+        let without_intensity = RegionAdjacencyGraph::new(&labelled_image, 0, None);
+        assert_eq!(without_intensity.edges[0].mean_boundary_intensity, None);
+
+        let color = Rgba([255, 255, 255, 255]);
+        let mut canvas = RgbaImage::new(5, 2);
+        draw_region_adjacency_graph_mut(&mut canvas, &graph, color);
+        assert_eq!(*canvas.get_pixel(4, 0), color);
+    }
+
+    #[test]
+    fn test_compare_segmentations() {
+        // Reference: 1 and 2 touch, 3 is alone, 4 is missed.
+        // Prediction: 10 merges 1 and 2, 3 is split into 20 and 21, 30 is false.
+        #[rustfmt::skip]
+        let reference = ImageBuffer::<Luma<u32>, _>::from_raw(8, 2, vec![
+            1, 1, 2, 2, 0, 3, 3, 0,
+            0, 0, 0, 0, 0, 3, 3, 4,
+        ]).unwrap();
+        #[rustfmt::skip]
+        let prediction = ImageBuffer::<Luma<u32>, _>::from_raw(8, 2, vec![
+            10, 10, 10, 10, 0, 20, 21, 0,
+            30, 0, 0, 0, 0, 20, 21, 0,
+        ]).unwrap();
+
+        let comparison = compare_segmentations(&reference, &prediction, 0, 0.5, 0.2);
+        let statuses = |objects: &[ObjectScore<u32>]| {
+            objects
+                .iter()
+                .map(|o| (o.label, o.status))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            statuses(&comparison.reference),
+            vec![
+                (1, ObjectStatus::Merged),
+                (2, ObjectStatus::Merged),
+                (3, ObjectStatus::Split),
+                (4, ObjectStatus::Missed)
+            ]
+        );
+        assert_eq!(
+            statuses(&comparison.prediction),
+            vec![
+                (10, ObjectStatus::Merged),
+                (20, ObjectStatus::Split),
+                (21, ObjectStatus::Split),
+                (30, ObjectStatus::False)
+            ]
+        );
+
+        let one = &comparison.reference[0];
+        assert_eq!(one.counterpart, Some(10));
+        assert_eq!(one.iou, 0.5);
+        assert!((one.dice - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(comparison.reference[3].counterpart, None);
+
+        let identical = compare_segmentations(&reference, &reference, 0, 0.5, 0.2);
+        assert!(
+            identical
+                .reference
+                .iter()
+                .all(|o| o.status == ObjectStatus::Matched && o.iou == 1.0)
+        );
+        assert!((identical.adjusted_rand_index - 1.0).abs() < 1e-12);
+        assert!(identical.variation_of_information.total.abs() < 1e-12);
+        assert!(comparison.adjusted_rand_index < 1.0);
+        assert!(comparison.variation_of_information.over_segmentation > 0.0);
+        assert!(comparison.variation_of_information.under_segmentation > 0.0);
+
+        let background = Rgba([0, 0, 0, 255]);
+        let drawn =
+            draw_segmentation_comparison(&reference, &prediction, 0, &comparison, background);
+        assert_eq!(*drawn.get_pixel(0, 0), Rgba([200, 0, 255, 255]));
+        assert_eq!(*drawn.get_pixel(5, 0), Rgba([255, 200, 0, 255]));
+        assert_eq!(*drawn.get_pixel(7, 1), Rgba([0, 0, 255, 255]));
+        assert_eq!(*drawn.get_pixel(0, 1), Rgba([255, 0, 0, 255]));
+        assert_eq!(*drawn.get_pixel(4, 0), background);
+
+        // Images with fewer than two pixels have no pairs to compare.
+        for (width, height) in [(0, 0), (1, 1)] {
+            let tiny = ImageBuffer::<Luma<u32>, _>::new(width, height);
+            let comparison = compare_segmentations(&tiny, &tiny, 0, 0.5, 0.2);
+            assert_eq!(comparison.adjusted_rand_index, 1.0);
+            assert_eq!(comparison.variation_of_information.total, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_label_contours_match_per_label_find_contours() {
+        // Label 1 is a ring around label 2; label 3 has two separate pieces.
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 0, 3, 0,
+            0, 1, 2, 2, 1, 0, 0, 0,
+            0, 1, 2, 2, 1, 0, 3, 3,
+            0, 1, 1, 1, 1, 0, 3, 3,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(8, 6, labels_vec).unwrap();
+
+        let contours = label_contours(&labelled_image, 0);
+        assert_eq!(contours.keys().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        for (&label, label_contours) in &contours {
+            let mask = GrayImage::from_fn(8, 6, |x, y| {
+                Luma([u8::from(labelled_image.get_pixel(x, y)[0] == label)])
+            });
+            let expected: Vec<Contour<i32>> = find_contours(&mask);
+            assert_eq!(label_contours.len(), expected.len());
+            for (contour, expected) in label_contours.iter().zip(&expected) {
+                assert_eq!(contour.points, expected.points);
+                assert_eq!(contour.border_type, expected.border_type);
+                assert_eq!(contour.parent, expected.parent);
+            }
+        }
+
+        let ring = &contours[&1];
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring[1].border_type, imageproc::contours::BorderType::Hole);
+        assert_eq!(ring[1].parent, Some(0));
+        assert_eq!(contours[&3].len(), 2);
+
+        let at_edge = ImageBuffer::<Luma<u32>, _>::from_raw(2, 2, vec![5, 5, 5, 0]).unwrap();
+        let edge_contours = &label_contours(&at_edge, 0)[&5];
+        assert_eq!(edge_contours.len(), 1);
+        assert_eq!(
+            edge_contours[0].border_type,
+            imageproc::contours::BorderType::Outer
+        );
+        assert_eq!(
+            edge_contours[0].points[0],
+            imageproc::point::Point::new(0, 0)
+        );
+    }
 }
@@ -0,0 +1,255 @@
+use crate::{
+    contours::signed_area,
+    geometry::{point_polygon_distance, segment_intersection},
+};
+use image::math::Rect;
+use imageproc::{contours::Contour, point::Point};
+use num_traits::AsPrimitive;
+use rstar::{
+    AABB, RTree,
+    primitives::{GeomWithData, Rectangle},
+};
+
+type IndexedBox = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+/// An R-tree over the bounding boxes of a set of contours.
+///
+/// Queries first select candidates by bounding box and then refine them against the
+/// contour polygons, so results are exact. Each contour is treated as the filled polygon
+/// it encloses, regardless of its `BorderType`; contours without points are never returned.
+///
+/// # Examples
+///
+/// ```
+/// use image_debug_utils::spatial_index::ContourIndex;
+/// use imageproc::{contours::{BorderType, Contour}, point::Point};
+///
+/// let square = |x: i32| Contour::new(
+///     vec![Point::new(x, 0), Point::new(x + 9, 0), Point::new(x + 9, 9), Point::new(x, 9)],
+///     BorderType::Outer,
+///     None,
+/// );
+/// let contours = vec![square(0), square(20), square(40)];
+/// let index = ContourIndex::new(&contours);
+///
+/// assert_eq!(index.at_point(Point::new(25.0, 5.0)), vec![1]);
+/// assert_eq!(index.nearest(Point::new(34.0, 5.0), 1), vec![(1, 5.0)]);
+/// ```
+pub struct ContourIndex<'a, T> {
+    contours: &'a [Contour<T>],
+    tree: RTree<IndexedBox>,
+}
+
+impl<'a, T> ContourIndex<'a, T>
+where
+    T: Copy + AsPrimitive<f64>,
+{
+    /// Bulk-loads the index. Building is `O(n log n)` in the number of contours.
+    pub fn new(contours: &'a [Contour<T>]) -> Self {
+        let boxes = contours
+            .iter()
+            .enumerate()
+            .filter_map(|(index, contour)| {
+                let first = contour.points.first()?;
+                let (mut min, mut max) = (
+                    [first.x.as_(), first.y.as_()],
+                    [first.x.as_(), first.y.as_()],
+                );
+                for p in &contour.points {
+                    let (x, y): (f64, f64) = (p.x.as_(), p.y.as_());
+                    min = [min[0].min(x), min[1].min(y)];
+                    max = [max[0].max(x), max[1].max(y)];
+                }
+                Some(GeomWithData::new(Rectangle::from_corners(min, max), index))
+            })
+            .collect();
+
+        ContourIndex {
+            contours,
+            tree: RTree::bulk_load(boxes),
+        }
+    }
+
+    /// The number of indexed contours, including those without points.
+    pub fn len(&self) -> usize {
+        self.contours.len()
+    }
+
+    /// Whether the index was built from an empty slice.
+    pub fn is_empty(&self) -> bool {
+        self.contours.is_empty()
+    }
+
+    /// Returns the contours whose polygon intersects `region`, in ascending index order.
+    ///
+    /// `region` is treated as the closed box from `(x, y)` to `(x + width, y + height)`.
+    pub fn in_region(&self, region: Rect) -> Vec<usize> {
+        let (left, top) = (region.x as f64, region.y as f64);
+        let (right, bottom) = (left + region.width as f64, top + region.height as f64);
+        let corners = [(left, top), (right, top), (right, bottom), (left, bottom)];
+
+        let mut hits: Vec<usize> = self
+            .tree
+            .locate_in_envelope_intersecting(AABB::from_corners([left, top], [right, bottom]))
+            .map(|candidate| candidate.data)
+            .filter(|&index| {
+                let polygon = self.polygon(index);
+                let vertex_inside = polygon
+                    .iter()
+                    .any(|&(x, y)| x >= left && x <= right && y >= top && y <= bottom);
+                vertex_inside
+                    || point_polygon_distance(corners[0], &polygon) == 0.0
+                    || edges(&polygon).any(|(a, b)| {
+                        edges(&corners).any(|(c, d)| segment_intersection(a, b, c, d).is_some())
+                    })
+            })
+            .collect();
+        hits.sort_unstable();
+        hits
+    }
+
+    /// Returns the contours whose polygon contains `point`, including its boundary.
+    ///
+    /// The hits are sorted by ascending polygon area, so for nested contours the innermost
+    /// comes first, which is usually the one meant by a click.
+    pub fn at_point(&self, point: Point<f64>) -> Vec<usize> {
+        let p = (point.x, point.y);
+        let mut hits: Vec<(usize, f64)> = self
+            .tree
+            .locate_all_at_point([point.x, point.y])
+            .filter_map(|candidate| {
+                let polygon = self.polygon(candidate.data);
+                (point_polygon_distance(p, &polygon) == 0.0).then(|| {
+                    let points = &self.contours[candidate.data].points;
+                    (candidate.data, signed_area(points).abs())
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        hits.into_iter().map(|(index, _)| index).collect()
+    }
+
+    /// Returns the `k` contours closest to `point` as `(index, distance)`, nearest first.
+    ///
+    /// The distance is measured to the polygon, so it is 0 for every contour containing
+    /// `point`. Equal distances are broken by the lower index.
+    pub fn nearest(&self, point: Point<f64>, k: usize) -> Vec<(usize, f64)> {
+        let p = (point.x, point.y);
+        let mut nearest: Vec<(usize, f64)> = Vec::with_capacity(k + 1);
+        if k == 0 {
+            return nearest;
+        }
+
+        for (candidate, box_distance_2) in self
+            .tree
+            .nearest_neighbor_iter_with_distance_2([point.x, point.y])
+        {
+            // The box distance is a lower bound for the polygon distance, and candidates
+            // come in ascending box distance, so no later candidate can do better.
+            if nearest.len() == k && box_distance_2.sqrt() > nearest[k - 1].1 {
+                break;
+            }
+            let distance = point_polygon_distance(p, &self.polygon(candidate.data));
+            let position = nearest.partition_point(|&(index, d)| {
+                d < distance || (d == distance && index < candidate.data)
+            });
+            nearest.insert(position, (candidate.data, distance));
+            nearest.truncate(k);
+        }
+
+        nearest
+    }
+
+    fn polygon(&self, index: usize) -> Vec<(f64, f64)> {
+        self.contours[index]
+            .points
+            .iter()
+            .map(|p| (p.x.as_(), p.y.as_()))
+            .collect()
+    }
+}
+
+/// The edges of a closed ring, joining the last point back to the first.
+fn edges(points: &[(f64, f64)]) -> impl Iterator<Item = ((f64, f64), (f64, f64))> + '_ {
+    points
+        .iter()
+        .copied()
+        .zip(points.iter().copied().cycle().skip(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::rect;
+    use imageproc::contours::BorderType;
+
+    fn polygon(points: &[(i32, i32)]) -> Contour<i32> {
+        Contour::new(
+            points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            BorderType::Outer,
+            None,
+        )
+    }
+
+    /// A big triangle whose bounding box covers a small square, a larger square around that one,
+    /// and an empty contour.
+    fn scene() -> Vec<Contour<i32>> {
+        vec![
+            polygon(&[(0, 0), (100, 0), (0, 100)]),
+            polygon(&[(70, 70), (80, 70), (80, 80), (70, 80)]),
+            polygon(&[(60, 60), (90, 60), (90, 90), (60, 90)]),
+            polygon(&[]),
+        ]
+    }
+
+    #[test]
+    fn test_in_region() {
+        let contours = scene();
+        let index = ContourIndex::new(&contours);
+        assert_eq!(index.len(), 4);
+
+        // Inside the triangle's bounding box but outside the triangle itself.
+        assert_eq!(index.in_region(rect(72, 72, 2, 2)), vec![1, 2]);
+        // A region fully inside the triangle, containing none of its vertices.
+        assert_eq!(index.in_region(rect(10, 10, 5, 5)), vec![0]);
+        // A thin region crossing the triangle's hypotenuse without containing a vertex.
+        assert_eq!(index.in_region(rect(30, 0, 20, 200)), vec![0]);
+        assert!(index.in_region(rect(200, 200, 10, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_at_point() {
+        let contours = scene();
+        let index = ContourIndex::new(&contours);
+
+        assert_eq!(index.at_point(Point::new(75.0, 75.0)), vec![1, 2]);
+        assert_eq!(index.at_point(Point::new(65.0, 65.0)), vec![2]);
+        assert_eq!(index.at_point(Point::new(50.0, 50.0)), vec![0]);
+        assert_eq!(index.at_point(Point::new(10.0, 10.0)), vec![0]);
+        assert!(index.at_point(Point::new(95.0, 10.0)).is_empty());
+    }
+
+    #[test]
+    fn test_nearest() {
+        let contours = scene();
+        let index = ContourIndex::new(&contours);
+
+        // The point is in the triangle's bounding box, but the larger square is closer.
+        assert_eq!(
+            index.nearest(Point::new(95.0, 75.0), 2),
+            vec![(2, 5.0), (1, 15.0)]
+        );
+        assert_eq!(index.nearest(Point::new(75.0, 75.0), 1), vec![(1, 0.0)]);
+        assert_eq!(index.nearest(Point::new(0.0, 0.0), 10).len(), 3);
+        assert!(index.nearest(Point::new(0.0, 0.0), 0).is_empty());
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let contours: Vec<Contour<i32>> = Vec::new();
+        let index = ContourIndex::new(&contours);
+        assert!(index.is_empty());
+        assert!(index.at_point(Point::new(0.0, 0.0)).is_empty());
+        assert!(index.nearest(Point::new(0.0, 0.0), 3).is_empty());
+    }
+}
@@ -0,0 +1,21 @@
+//! Builders and assertions shared by the unit tests of several modules.
+
+use image::math::Rect;
+
+pub(crate) fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// A 10x10 box at `(x, y)`.
+pub(crate) fn cell(x: u32, y: u32) -> Rect {
+    rect(x, y, 10, 10)
+}
+
+pub(crate) fn assert_float_eq(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "expected {b}, got {a}");
+}
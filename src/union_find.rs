@@ -0,0 +1,73 @@
+/// A disjoint-set forest with path compression and union by size.
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(len: usize) -> Self {
+        UnionFind {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    pub(crate) fn find(&mut self, mut x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+
+    /// Assigns every element a compact cluster index, numbering clusters in order of their
+    /// first element.
+    pub(crate) fn cluster_indices(&mut self) -> (Vec<usize>, usize) {
+        let len = self.parent.len();
+        let mut cluster_of_root = vec![usize::MAX; len];
+        let mut clusters = Vec::with_capacity(len);
+        let mut count = 0;
+        for i in 0..len {
+            let root = self.find(i);
+            if cluster_of_root[root] == usize::MAX {
+                cluster_of_root[root] = count;
+                count += 1;
+            }
+            clusters.push(cluster_of_root[root]);
+        }
+        (clusters, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find_clusters() {
+        let mut sets = UnionFind::new(6);
+        sets.union(4, 1);
+        sets.union(1, 5);
+        sets.union(2, 3);
+        assert_eq!(sets.find(5), sets.find(4));
+        assert_ne!(sets.find(0), sets.find(1));
+        assert_eq!(sets.cluster_indices(), (vec![0, 1, 2, 2, 1, 1], 3));
+    }
+}
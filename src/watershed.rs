@@ -0,0 +1,434 @@
+use crate::region_labelling::Label;
+use image::{GrayImage, ImageBuffer, Luma};
+use imageproc::distance_transform::euclidean_squared_distance_transform;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// How [`split_touching`] places one marker per object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerMethod {
+    /// The h-maxima of the distance map: every peak that rises at least `h` pixels above
+    /// the saddle to a higher peak becomes a marker. Larger values merge more. `h` must be
+    /// non-negative and finite.
+    HMaxima { h: f32 },
+    /// Local maxima of the distance map, greedily taken by descending distance and at least
+    /// `min_distance` pixels apart within the same 8-connected component. `min_distance`
+    /// must be non-negative and finite.
+    MinDistance { min_distance: f32 },
+}
+
+/// Intermediate images of [`split_touching_debug`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitDebug {
+    /// The Euclidean distance of every foreground pixel to the nearest background pixel.
+    /// The area outside the image counts as background.
+    pub distance: ImageBuffer<Luma<f32>, Vec<f32>>,
+    /// The markers, labelled like the final objects, 0 elsewhere.
+    pub markers: ImageBuffer<Luma<u32>, Vec<u32>>,
+    /// 255 on pixels that border a different new object within the same component,
+    /// including diagonally.
+    pub watershed_lines: GrayImage,
+}
+
+/// Splits clumped objects with a distance-transform watershed.
+///
+/// Computes the distance transform of the foreground, places markers at its peaks and
+/// floods the foreground from the markers in order of descending distance. Components are
+/// the 8-connected regions of equally labelled pixels. Flooding never leaves a component, so
+/// touching objects that are already separate stay separate, and every component gets at
+/// least one marker.
+///
+/// # Arguments
+/// * `labelled_image` - The labelled image generated by a function like `imageproc::region_labelling::connected_components`.
+/// * `background_label` - The label of background pixels.
+/// * `markers` - How to find one marker per object.
+///
+/// # Returns
+/// A new label image with labels `1..=K`, numbered in row-major order of their markers.
+///
+/// # Panics
+///
+/// Panics if the `h` or `min_distance` of `markers` is negative or not finite.
+pub fn split_touching<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    markers: MarkerMethod,
+) -> ImageBuffer<Luma<u32>, Vec<u32>> {
+    split_touching_debug(labelled_image, background_label, markers).0
+}
+
+/// Like [`split_touching`], but also returns the distance map, markers and watershed lines.
+///
+/// # Panics
+///
+/// Panics if the `h` or `min_distance` of `markers` is negative or not finite.
+pub fn split_touching_debug<L: Label>(
+    labelled_image: &ImageBuffer<Luma<L>, Vec<L>>,
+    background_label: L,
+    markers: MarkerMethod,
+) -> (ImageBuffer<Luma<u32>, Vec<u32>>, SplitDebug) {
+    match markers {
+        MarkerMethod::HMaxima { h } => assert!(
+            h.is_finite() && h >= 0.0,
+            "h must be a non-negative finite number, got {h}"
+        ),
+        MarkerMethod::MinDistance { min_distance } => assert!(
+            min_distance.is_finite() && min_distance >= 0.0,
+            "min_distance must be a non-negative finite number, got {min_distance}"
+        ),
+    }
+
+    let (width, height) = labelled_image.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let labels: &[L] = labelled_image.as_raw();
+    let foreground: Vec<bool> = labels.iter().map(|&l| l != background_label).collect();
+
+    // The background (with a frame around the image) is the "foreground" of imageproc's
+    // transform, which measures the distance to the nearest non-zero pixel.
+    let background = GrayImage::from_fn(width + 2, height + 2, |x, y| {
+        let inside = (1..=width).contains(&x) && (1..=height).contains(&y);
+        Luma([u8::from(
+            !inside || !foreground[(y as usize - 1) * w + x as usize - 1],
+        )])
+    });
+    let squared = euclidean_squared_distance_transform(&background);
+    let distance: Vec<f32> = (0..w * h)
+        .map(|i| squared.get_pixel((i % w) as u32 + 1, (i / w) as u32 + 1)[0].sqrt() as f32)
+        .collect();
+
+    let neighbors_8 = |index: usize| {
+        let (x, y) = ((index % w) as i64, (index / w) as i64);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(move |&(nx, ny)| {
+                (nx, ny) != (x, y) && nx >= 0 && ny >= 0 && nx < w as i64 && ny < h as i64
+            })
+            .map(move |(nx, ny)| ny as usize * w + nx as usize)
+    };
+
+    // The 8-connected components of equally labelled pixels. Markers, flooding and lines
+    // never cross between components.
+    let mut component = vec![0u32; w * h];
+    let mut component_count = 0;
+    for start in 0..w * h {
+        if !foreground[start] || component[start] != 0 {
+            continue;
+        }
+        component_count += 1;
+        component[start] = component_count;
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            for neighbor in neighbors_8(index) {
+                if component[neighbor] == 0
+                    && foreground[neighbor]
+                    && labels[neighbor] == labels[index]
+                {
+                    component[neighbor] = component_count;
+                    stack.push(neighbor);
+                }
+            }
+        }
+    }
+
+    let is_marker: Vec<bool> = match markers {
+        MarkerMethod::HMaxima { h: dynamic } => {
+            // Reconstruction by dilation of `distance - h` under `distance`, processing
+            // pixels from the highest value down.
+            let mut reconstruction: Vec<f32> = distance
+                .iter()
+                .zip(&foreground)
+                .map(|(&d, &f)| if f { d - dynamic } else { 0.0 })
+                .collect();
+            let mut queue: BinaryHeap<(OrderedDistance, Reverse<usize>)> = (0..w * h)
+                .filter(|&i| foreground[i])
+                .map(|i| (OrderedDistance(reconstruction[i]), Reverse(i)))
+                .collect();
+            while let Some((OrderedDistance(value), Reverse(index))) = queue.pop() {
+                if value < reconstruction[index] {
+                    continue;
+                }
+                for neighbor in neighbors_8(index) {
+                    let raised = value.min(distance[neighbor]);
+                    if foreground[neighbor]
+                        && component[neighbor] == component[index]
+                        && raised > reconstruction[neighbor]
+                    {
+                        reconstruction[neighbor] = raised;
+                        queue.push((OrderedDistance(raised), Reverse(neighbor)));
+                    }
+                }
+            }
+            // The markers are the regional maxima of the reconstruction: plateaus without a
+            // higher neighbor. Plateau values are exact copies, so equality is safe.
+            let mut is_marker = vec![false; w * h];
+            let mut visited = vec![false; w * h];
+            for start in (0..w * h).filter(|&i| foreground[i]) {
+                if visited[start] {
+                    continue;
+                }
+                visited[start] = true;
+                let (mut plateau, mut stack, mut is_maximum) = (vec![start], vec![start], true);
+                while let Some(index) = stack.pop() {
+                    for neighbor in neighbors_8(index) {
+                        if !foreground[neighbor] || component[neighbor] != component[index] {
+                            continue;
+                        }
+                        if reconstruction[neighbor] > reconstruction[index] {
+                            is_maximum = false;
+                        } else if reconstruction[neighbor] == reconstruction[index]
+                            && !visited[neighbor]
+                        {
+                            visited[neighbor] = true;
+                            plateau.push(neighbor);
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+                if is_maximum {
+                    for index in plateau {
+                        is_marker[index] = true;
+                    }
+                }
+            }
+            is_marker
+        }
+        MarkerMethod::MinDistance { min_distance } => {
+            let mut candidates: Vec<usize> = (0..w * h)
+                .filter(|&i| {
+                    foreground[i]
+                        && neighbors_8(i)
+                            .all(|n| component[n] != component[i] || distance[n] <= distance[i])
+                })
+                .collect();
+            candidates.sort_by(|&a, &b| distance[b].total_cmp(&distance[a]).then(a.cmp(&b)));
+
+            let cell_size = min_distance.max(1.0);
+            let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+            let mut is_marker = vec![false; w * h];
+            for candidate in candidates {
+                let (x, y) = ((candidate % w) as f32, (candidate / w) as f32);
+                let cell = ((x / cell_size) as i64, (y / cell_size) as i64);
+                let too_close = (-1..=1).any(|dy| {
+                    (-1..=1).any(|dx| {
+                        grid.get(&(cell.0 + dx, cell.1 + dy))
+                            .is_some_and(|accepted| {
+                                accepted.iter().any(|&a| {
+                                    let (ax, ay) = ((a % w) as f32, (a / w) as f32);
+                                    component[a] == component[candidate]
+                                        && (ax - x).hypot(ay - y) < min_distance
+                                })
+                            })
+                    })
+                });
+                if !too_close {
+                    grid.entry(cell).or_default().push(candidate);
+                    is_marker[candidate] = true;
+                }
+            }
+            is_marker
+        }
+    };
+
+    // Number the 8-connected marker regions in row-major order.
+    let mut output = vec![0u32; w * h];
+    let mut next_label = 0;
+    for start in 0..w * h {
+        if !is_marker[start] || output[start] != 0 {
+            continue;
+        }
+        next_label += 1;
+        output[start] = next_label;
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            for neighbor in neighbors_8(index) {
+                if is_marker[neighbor]
+                    && output[neighbor] == 0
+                    && component[neighbor] == component[index]
+                {
+                    output[neighbor] = next_label;
+                    stack.push(neighbor);
+                }
+            }
+        }
+    }
+    let marker_image = output.clone();
+
+    // Flood from the markers, highest distance first and first come first served on ties.
+    let mut order = 0usize;
+    let mut queue: BinaryHeap<(OrderedDistance, Reverse<usize>, usize)> = BinaryHeap::new();
+    for index in (0..w * h).filter(|&i| output[i] != 0) {
+        queue.push((OrderedDistance(distance[index]), Reverse(order), index));
+        order += 1;
+    }
+    while let Some((_, _, index)) = queue.pop() {
+        for neighbor in neighbors_8(index) {
+            if foreground[neighbor]
+                && output[neighbor] == 0
+                && component[neighbor] == component[index]
+            {
+                output[neighbor] = output[index];
+                queue.push((
+                    OrderedDistance(distance[neighbor]),
+                    Reverse(order),
+                    neighbor,
+                ));
+                order += 1;
+            }
+        }
+    }
+
+    let watershed_lines = GrayImage::from_fn(width, height, |x, y| {
+        let index = y as usize * w + x as usize;
+        let on_line = output[index] != 0
+            && neighbors_8(index).any(|n| {
+                output[n] != 0 && output[n] != output[index] && component[n] == component[index]
+            });
+        Luma([if on_line { 255 } else { 0 }])
+    });
+
+    let to_image = |values: Vec<u32>| ImageBuffer::from_raw(width, height, values).unwrap();
+    let debug = SplitDebug {
+        distance: ImageBuffer::from_raw(width, height, distance).unwrap(),
+        markers: to_image(marker_image),
+        watershed_lines,
+    };
+    (to_image(output), debug)
+}
+
+/// An `f32` that is totally ordered, for priority queues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OrderedDistance(f32);
+
+impl Eq for OrderedDistance {}
+
+impl PartialOrd for OrderedDistance {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedDistance {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Two overlapping discs of radius 6 whose centers are 10 pixels apart, labelled as
+    /// one component, and a separate small disc.
+    fn touching_discs() -> ImageBuffer<Luma<u32>, Vec<u32>> {
+        ImageBuffer::from_fn(40, 20, |x, y| {
+            let inside = |cx: f32, cy: f32, r: f32| (x as f32 - cx).hypot(y as f32 - cy) <= r;
+            if inside(8.0, 10.0, 6.0) || inside(18.0, 10.0, 6.0) {
+                Luma([1])
+            } else if inside(33.0, 10.0, 3.0) {
+                Luma([2])
+            } else {
+                Luma([0])
+            }
+        })
+    }
+
+    #[test]
+    fn test_split_touching() {
+        let labelled_image = touching_discs();
+
+        for method in [
+            MarkerMethod::HMaxima { h: 1.0 },
+            MarkerMethod::MinDistance { min_distance: 5.0 },
+        ] {
+            let split = split_touching(&labelled_image, 0, method);
+            let left = split.get_pixel(8, 10)[0];
+            let right = split.get_pixel(18, 10)[0];
+            let small = split.get_pixel(33, 10)[0];
+            assert_eq!((left, right, small), (1, 2, 3), "{method:?}");
+            assert_eq!(split.get_pixel(0, 0)[0], 0);
+            // The split runs through the neck between the two discs.
+            assert_eq!(split.get_pixel(11, 10)[0], left);
+            assert_eq!(split.get_pixel(15, 10)[0], right);
+        }
+
+        // A large h keeps the clump together, but every component keeps a marker.
+        let merged = split_touching(&labelled_image, 0, MarkerMethod::HMaxima { h: 10.0 });
+        assert_eq!(merged.get_pixel(8, 10)[0], merged.get_pixel(18, 10)[0]);
+        assert_ne!(merged.get_pixel(33, 10)[0], 0);
+    }
+
+    #[test]
+    fn test_split_touching_debug() {
+        let labelled_image = touching_discs();
+        let (split, debug) =
+            split_touching_debug(&labelled_image, 0, MarkerMethod::HMaxima { h: 1.0 });
+
+        assert_eq!(debug.distance.get_pixel(0, 0)[0], 0.0);
+        assert!(debug.distance.get_pixel(8, 10)[0] >= 6.0);
+        assert_eq!(debug.markers.get_pixel(8, 10)[0], split.get_pixel(8, 10)[0]);
+        assert_eq!(debug.markers.get_pixel(11, 10)[0], 0);
+        assert!(debug.watershed_lines.pixels().any(|p| p[0] == 255));
+        assert!(
+            debug
+                .watershed_lines
+                .enumerate_pixels()
+                .filter(|(_, _, p)| p[0] == 255)
+                .all(|(x, _, _)| (12..=14).contains(&x))
+        );
+    }
+
+    #[test]
+    fn test_split_touching_diagonal_and_repeated_labels() {
+        // A diagonal line and two separate blobs that share label 2.
+        #[rustfmt::skip]
+        let labels_vec = vec![
+            1, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 2, 2, 0,
+            0, 0, 1, 0, 0, 2, 2, 0,
+            0, 0, 0, 1, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0, 2, 2,
+            0, 0, 0, 0, 0, 1, 2, 2,
+        ];
+        let labelled_image = ImageBuffer::<Luma<u32>, _>::from_raw(8, 6, labels_vec).unwrap();
+
+        for method in [
+            MarkerMethod::HMaxima { h: 1.0 },
+            MarkerMethod::MinDistance { min_distance: 10.0 },
+        ] {
+            let split = split_touching(&labelled_image, 0, method);
+            for (x, y, &Luma([label])) in labelled_image.enumerate_pixels() {
+                assert_eq!(
+                    split.get_pixel(x, y)[0] != 0,
+                    label != 0,
+                    "{method:?} at ({x}, {y})"
+                );
+            }
+            let diagonal: HashSet<u32> = (0..6).map(|i| split.get_pixel(i, i)[0]).collect();
+            assert_eq!(diagonal.len(), 1, "{method:?}");
+            assert_ne!(
+                split.get_pixel(5, 1)[0],
+                split.get_pixel(6, 4)[0],
+                "{method:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "h must be a non-negative finite number")]
+    fn test_split_touching_rejects_nan_h() {
+        split_touching(&touching_discs(), 0, MarkerMethod::HMaxima { h: f32::NAN });
+    }
+
+    #[test]
+    #[should_panic(expected = "min_distance must be a non-negative finite number")]
+    fn test_split_touching_rejects_negative_min_distance() {
+        split_touching(
+            &touching_discs(),
+            0,
+            MarkerMethod::MinDistance { min_distance: -1.0 },
+        );
+    }
+}